@@ -0,0 +1,294 @@
+use crate::github::GitHubError;
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT, USER_AGENT};
+use serde::Deserialize;
+use serde_json::Value;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Scopes requested by `git_mate auth login`.
+pub const LOGIN_SCOPES: &str = "repo workflow read:org";
+
+const DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Response to the device authorization request.
+#[derive(Debug, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+/// Token granted at the end of the device flow.
+#[derive(Debug)]
+pub struct AccessToken {
+    pub token: String,
+    pub scopes: Vec<String>,
+}
+
+/// Client for GitHub's OAuth device authorization flow. The endpoints live
+/// on the web host (e.g. https://github.com/login/device/code), not the API.
+pub struct DeviceFlow {
+    client: Client,
+    web_url: String,
+    client_id: String,
+}
+
+impl DeviceFlow {
+    pub fn new(web_url: &str, client_id: &str) -> Self {
+        DeviceFlow {
+            client: Client::new(),
+            web_url: web_url.trim_end_matches('/').to_string(),
+            client_id: client_id.to_string(),
+        }
+    }
+
+    /// Step 1: ask for a user code to show to the user.
+    pub fn request_code(&self, scopes: &str) -> Result<DeviceCode, GitHubError> {
+        let json = self.post(
+            "/login/device/code",
+            &[("client_id", self.client_id.as_str()), ("scope", scopes)],
+        )?;
+        if let Some(error) = json["error"].as_str() {
+            return Err(oauth_error(error, &json));
+        }
+        serde_json::from_value(json).map_err(|e| GitHubError::Decode(e.to_string()))
+    }
+
+    /// Step 2: poll until the user authorizes the device, the code expires
+    /// or access is denied.
+    pub fn poll_token(&self, code: &DeviceCode) -> Result<AccessToken, GitHubError> {
+        let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+        let mut interval = code.interval;
+
+        loop {
+            if Instant::now() >= deadline {
+                return Err(GitHubError::Auth {
+                    status: 0,
+                    message: "the device code expired before it was authorized".to_string(),
+                });
+            }
+            thread::sleep(Duration::from_secs(interval));
+
+            let json = self.post(
+                "/login/oauth/access_token",
+                &[
+                    ("client_id", self.client_id.as_str()),
+                    ("device_code", code.device_code.as_str()),
+                    ("grant_type", DEVICE_GRANT_TYPE),
+                ],
+            )?;
+
+            if let Some(token) = json["access_token"].as_str() {
+                let scopes = json["scope"]
+                    .as_str()
+                    .unwrap_or("")
+                    .split([',', ' '])
+                    .filter(|scope| !scope.is_empty())
+                    .map(|scope| scope.to_string())
+                    .collect();
+                return Ok(AccessToken {
+                    token: token.to_string(),
+                    scopes,
+                });
+            }
+
+            match json["error"].as_str() {
+                Some("authorization_pending") => {}
+                // GitHub asks us to back off by another five seconds
+                Some("slow_down") => {
+                    interval = json["interval"].as_u64().unwrap_or(interval + 5);
+                }
+                Some(error) => return Err(oauth_error(error, &json)),
+                None => {
+                    return Err(GitHubError::Decode(
+                        "no access token or error in response".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<Value, GitHubError> {
+        let res = self
+            .client
+            .post(format!("{}{}", self.web_url, path))
+            .header(ACCEPT, "application/json")
+            .header(USER_AGENT, "Git-Mate-CLI")
+            .form(form)
+            .send()
+            .map_err(|e| GitHubError::Network(e.to_string()))?;
+
+        let status = res.status();
+        if status.as_u16() == 404 {
+            return Err(GitHubError::NotFound {
+                message: format!("{} (is the device flow available on this host?)", path),
+            });
+        }
+        let json: Value = res.json().map_err(|e| GitHubError::Decode(e.to_string()))?;
+        if !status.is_success() && json["error"].is_null() {
+            return Err(GitHubError::Api {
+                status: status.as_u16(),
+                message: json["message"].as_str().unwrap_or("").to_string(),
+            });
+        }
+        Ok(json)
+    }
+}
+
+fn oauth_error(error: &str, json: &Value) -> GitHubError {
+    let message = json["error_description"]
+        .as_str()
+        .map(|description| format!("{}: {}", error, description))
+        .unwrap_or_else(|| error.to_string());
+    match error {
+        "access_denied" | "expired_token" | "incorrect_client_credentials" => {
+            GitHubError::Auth { status: 0, message }
+        }
+        "device_flow_disabled" | "unsupported_grant_type" | "incorrect_device_code" => {
+            GitHubError::Validation {
+                message,
+                errors: Vec::new(),
+            }
+        }
+        _ => GitHubError::Api { status: 0, message },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    // a stand-in for the device flow endpoints that answers each request
+    // with the next of `responses` and returns the requests it got, as
+    // "<path> <form body>"
+    fn serve(responses: &[&str]) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let responses: Vec<String> = responses.iter().map(|r| r.to_string()).collect();
+
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for body in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut form = vec![0; length];
+                reader.read_exact(&mut form).unwrap();
+                let path = request_line.split(' ').nth(1).unwrap_or("").to_string();
+                requests.push(format!("{} {}", path, String::from_utf8(form).unwrap()));
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    fn code(interval: u64) -> DeviceCode {
+        DeviceCode {
+            device_code: "dc".to_string(),
+            user_code: "ABCD-1234".to_string(),
+            verification_uri: "https://github.com/login/device".to_string(),
+            expires_in: 60,
+            interval,
+        }
+    }
+
+    #[test]
+    fn request_code() {
+        let (url, server) = serve(&[r#"{"device_code": "dc", "user_code": "ABCD-1234",
+            "verification_uri": "https://github.com/login/device", "expires_in": 900}"#]);
+        let code = DeviceFlow::new(&url, "client")
+            .request_code(LOGIN_SCOPES)
+            .unwrap();
+        assert_eq!(code.user_code, "ABCD-1234");
+        assert_eq!(code.interval, 5);
+        assert_eq!(
+            server.join().unwrap(),
+            ["/login/device/code client_id=client&scope=repo+workflow+read%3Aorg"]
+        );
+    }
+
+    #[test]
+    fn polls_until_the_token_is_granted() {
+        let (url, server) = serve(&[
+            r#"{"error": "authorization_pending"}"#,
+            r#"{"error": "slow_down", "interval": 1}"#,
+            r#"{"access_token": "gho_x", "token_type": "bearer", "scope": "repo,workflow"}"#,
+        ]);
+        let started = Instant::now();
+        let token = DeviceFlow::new(&url, "client")
+            .poll_token(&code(0))
+            .unwrap();
+
+        assert_eq!(token.token, "gho_x");
+        assert_eq!(token.scopes, ["repo", "workflow"]);
+        // the interval from slow_down is used for the next poll
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|request| request
+            == "/login/oauth/access_token client_id=client&device_code=dc&grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Adevice_code"));
+    }
+
+    #[test]
+    fn denied_and_expired_codes_are_auth_errors() {
+        for error in ["access_denied", "expired_token"] {
+            let (url, server) = serve(&[&format!(
+                r#"{{"error": "{}", "error_description": "nope"}}"#,
+                error
+            )]);
+            match DeviceFlow::new(&url, "client").poll_token(&code(0)) {
+                Err(GitHubError::Auth { message, .. }) => {
+                    assert_eq!(message, format!("{}: nope", error))
+                }
+                other => panic!("{}: {:?}", error, other),
+            }
+            server.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn oauth_error_kinds() {
+        let json = Value::Null;
+        assert!(matches!(
+            oauth_error("device_flow_disabled", &json),
+            GitHubError::Validation { .. }
+        ));
+        assert!(matches!(
+            oauth_error("something_new", &json),
+            GitHubError::Api { .. }
+        ));
+    }
+}
@@ -0,0 +1,630 @@
+use crate::dry_run;
+use crate::git::{get_origin, RemoteInfo};
+use crate::project::{load_project_config, ProjectConfig};
+use colored::*;
+use dirs::home_dir;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str;
+use std::sync::OnceLock;
+
+const CONFIG_DIR: &str = ".git_mate";
+const CONFIG_FILE: &str = "config.json";
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+const API_URL_ENV: &str = "GIT_MATE_API_URL";
+const TOKEN_ENV_VARS: &[&str] = &["GITHUB_TOKEN", "GH_TOKEN"];
+const PROFILE_ENV: &str = "GIT_MATE_PROFILE";
+const OAUTH_CLIENT_ID_ENV: &str = "GIT_MATE_OAUTH_CLIENT_ID";
+pub const DEFAULT_PROFILE: &str = "default";
+
+static SELECTED_PROFILE: OnceLock<String> = OnceLock::new();
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub github_token: String,
+    #[serde(default)]
+    pub username: String,
+    pub default_branch: Option<String>,
+    pub default_license: Option<String>,
+    pub api_url: Option<String>,
+    /// User or organization `init` creates repositories under by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_owner: Option<String>,
+    /// Remote URL protocol for new repositories: "ssh" or "https". When unset,
+    /// SSH is used if it authenticates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_protocol: Option<String>,
+    /// Name filled into LICENSE files; defaults to the GitHub profile name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copyright_holder: Option<String>,
+    /// Shell command whose stdout is the token, e.g. `pass show github`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_command: Option<String>,
+    /// Client ID of the OAuth App used by `git_mate auth login`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth_client_id: Option<String>,
+    /// Repository owners (users or orgs) this profile is picked for automatically.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    /// Named account profiles. The top-level fields act as the default profile.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Config>,
+
+    #[serde(skip)]
+    resolved_token: Option<(String, TokenSource)>,
+    #[serde(skip)]
+    active_profile: Option<String>,
+    /// Settings from the repository's `.git_mate.toml`, already layered
+    /// over the fields above by `load_config`.
+    #[serde(skip)]
+    pub project: ProjectConfig,
+}
+
+/// Where the token used for API calls came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenSource {
+    Env(&'static str),
+    Command,
+    ConfigFile,
+    GitCredential,
+}
+
+impl std::fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenSource::Env(name) => write!(f, "environment variable {}", name),
+            TokenSource::Command => write!(f, "token_command"),
+            TokenSource::ConfigFile => write!(f, "config file"),
+            TokenSource::GitCredential => write!(f, "git credential helper"),
+        }
+    }
+}
+
+impl Config {
+    // base URL for REST calls: env override, then config, then github.com
+    pub fn api_url(&self) -> String {
+        std::env::var(API_URL_ENV)
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .or_else(|| self.api_url.clone())
+            .unwrap_or_else(|| DEFAULT_API_URL.to_string())
+            .trim()
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    // base URL for links shown to the user
+    pub fn web_url(&self) -> String {
+        web_url_from_api_url(&self.api_url())
+    }
+
+    /// The token to authenticate with, wherever it came from.
+    pub fn token(&self) -> &str {
+        match &self.resolved_token {
+            Some((token, _)) => token,
+            None => &self.github_token,
+        }
+    }
+
+    /// Authenticate with the token in the file even when another source
+    /// takes precedence, e.g. to check a token before it is saved.
+    pub fn use_file_token(&mut self) {
+        if !self.github_token.is_empty() {
+            self.resolved_token = Some((self.github_token.clone(), TokenSource::ConfigFile));
+        }
+    }
+
+    pub fn token_source(&self) -> Option<&TokenSource> {
+        self.resolved_token.as_ref().map(|(_, source)| source)
+    }
+
+    /// OAuth App client ID for the device flow: env override, then config.
+    pub fn oauth_client_id(&self) -> Option<String> {
+        std::env::var(OAUTH_CLIENT_ID_ENV)
+            .ok()
+            .or_else(|| self.oauth_client_id.clone())
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+    }
+
+    /// Name of the profile this config was loaded for.
+    pub fn active_profile(&self) -> &str {
+        self.active_profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    // web host this config talks to, ignoring the env override
+    fn stored_host(&self) -> String {
+        let api_url = self.api_url.as_deref().unwrap_or(DEFAULT_API_URL);
+        url_host(&web_url_from_api_url(api_url)).to_string()
+    }
+
+    fn matches_owner(&self, owner: &str) -> bool {
+        self.owners
+            .iter()
+            .chain(std::iter::once(&self.username))
+            .any(|candidate| candidate.eq_ignore_ascii_case(owner))
+    }
+
+    // pick the profile for a remote: first one whose host and owner both
+    // match, then one whose owner matches (covers ssh host aliases), then the
+    // only one on that host. `None` means the top-level default profile.
+    fn profile_for_remote(&self, remote: &RemoteInfo) -> Option<String> {
+        let on_host = |config: &Config| config.stored_host().eq_ignore_ascii_case(&remote.host);
+        let candidates: Vec<(Option<&String>, &Config)> = std::iter::once((None, self))
+            .chain(
+                self.profiles
+                    .iter()
+                    .map(|(name, profile)| (Some(name), profile)),
+            )
+            .collect();
+
+        let found = candidates
+            .iter()
+            .find(|(_, config)| on_host(config) && config.matches_owner(&remote.owner))
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find(|(_, config)| config.matches_owner(&remote.owner))
+            })
+            .or_else(|| {
+                let mut on_remote_host = candidates.iter().filter(|(_, config)| on_host(config));
+                match (on_remote_host.next(), on_remote_host.next()) {
+                    (Some(only), None) => Some(only),
+                    _ => None,
+                }
+            });
+
+        found.and_then(|(name, _)| name.cloned())
+    }
+
+    // replace the account fields with the profile's; preferences such as
+    // the default branch fall back to the top-level values
+    fn into_profile(mut self, name: &str) -> Result<Config, String> {
+        let mut profile = self
+            .profiles
+            .remove(name)
+            .ok_or_else(|| format!("Unknown profile '{}'", name))?;
+        profile.default_branch = profile.default_branch.or(self.default_branch);
+        profile.default_license = profile.default_license.or(self.default_license);
+        profile.oauth_client_id = profile.oauth_client_id.or(self.oauth_client_id);
+        profile.git_protocol = profile.git_protocol.or(self.git_protocol);
+        profile.copyright_holder = profile.copyright_holder.or(self.copyright_holder);
+        profile.profiles = BTreeMap::new();
+        profile.active_profile = Some(name.to_string());
+        Ok(profile)
+    }
+
+    /// Look up the token in order: GITHUB_TOKEN/GH_TOKEN, `token_command`,
+    /// the config file, then `git credential fill` for the API host.
+    pub fn resolve_token(&mut self) -> Result<(), String> {
+        self.resolved_token = None;
+
+        for name in TOKEN_ENV_VARS {
+            if let Ok(token) = std::env::var(name) {
+                if !token.trim().is_empty() {
+                    self.resolved_token = Some((token.trim().to_string(), TokenSource::Env(name)));
+                    return Ok(());
+                }
+            }
+        }
+
+        if let Some(command) = &self.token_command {
+            let token = token_from_command(command)?;
+            self.resolved_token = Some((token, TokenSource::Command));
+            return Ok(());
+        }
+
+        if !self.github_token.is_empty() {
+            self.resolved_token = Some((self.github_token.clone(), TokenSource::ConfigFile));
+            return Ok(());
+        }
+
+        if let Some(token) = token_from_git_credential(url_host(&self.web_url())) {
+            self.resolved_token = Some((token, TokenSource::GitCredential));
+        }
+        Ok(())
+    }
+}
+
+fn token_from_command(command: &str) -> Result<String, String> {
+    let output = shell_command(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("failed to run token_command: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("token_command exited with {}", output.status));
+    }
+
+    let token = str::from_utf8(&output.stdout)
+        .map_err(|e| e.to_string())?
+        .lines()
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    if token.is_empty() {
+        Err("token_command printed no token".to_string())
+    } else {
+        Ok(token)
+    }
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
+
+// ask git's configured credential helpers for a password for `host`,
+// without letting git fall back to an interactive prompt
+fn token_from_git_credential(host: &str) -> Option<String> {
+    let mut child = Command::new("git")
+        .args(["credential", "fill"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "never")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    {
+        let stdin = child.stdin.as_mut()?;
+        write!(stdin, "protocol=https\nhost={}\n\n", host).ok()?;
+    }
+
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    str::from_utf8(&output.stdout)
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("password="))
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+fn get_config_path() -> Result<PathBuf, String> {
+    let home = home_dir().ok_or_else(|| "Could not find home directory".to_string())?;
+    let config_dir = home.join(CONFIG_DIR);
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
+        restrict_permissions(&config_dir, 0o700)?;
+    }
+    Ok(config_dir.join(CONFIG_FILE))
+}
+
+/// Directory of the user's own workflow templates, `~/.git_mate/workflows`.
+pub fn workflow_templates_dir() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(CONFIG_DIR).join("workflows"))
+}
+
+/// Read the config file as stored, without resolving the token.
+pub fn read_config() -> Result<Option<Config>, String> {
+    let config_path = get_config_path()?;
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    warn_if_readable_by_others(&config_path);
+    let config_str = fs::read_to_string(config_path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&config_str)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+/// Select a profile by name for this run (from `--profile`).
+pub fn set_profile(name: Option<&str>) {
+    if let Some(name) = name {
+        let _ = SELECTED_PROFILE.set(name.to_string());
+    }
+}
+
+/// Profile requested with `--profile` or GIT_MATE_PROFILE, if any.
+pub fn requested_profile() -> Option<String> {
+    SELECTED_PROFILE
+        .get()
+        .cloned()
+        .or_else(|| std::env::var(PROFILE_ENV).ok())
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// Load the config for the requested profile (from `--profile`, then
+/// `.git_mate.toml`, then the one matching the `origin` remote), layer the
+/// project settings over it and resolve the token. Works without a config
+/// file as long as a token is available from another source.
+pub fn load_config() -> Result<Config, String> {
+    let config = load_effective_config()?;
+    if config.token().is_empty() {
+        return Err(
+            "No GitHub token found. Please run 'git_mate config' first or set GITHUB_TOKEN."
+                .to_string(),
+        );
+    }
+    Ok(config)
+}
+
+/// Like `load_config`, but a missing token is not an error.
+pub fn load_effective_config() -> Result<Config, String> {
+    let file = read_config()?.unwrap_or_default();
+    let project = load_project_config()?;
+
+    let profile = match requested_profile().or_else(|| project.profile.clone()) {
+        Some(name) => Some(name),
+        None => get_origin()
+            .ok()
+            .and_then(|remote| file.profile_for_remote(&remote)),
+    };
+    let mut config = match profile {
+        Some(name) if name != DEFAULT_PROFILE => file.into_profile(&name)?,
+        _ => file,
+    };
+
+    config.default_branch = project.default_branch.clone().or(config.default_branch);
+    config.default_license = project.default_license.clone().or(config.default_license);
+    config.project = project;
+
+    config.resolve_token()?;
+    Ok(config)
+}
+
+/// Show just enough of a token to recognise it.
+pub fn mask_token(token: &str) -> String {
+    if token.is_empty() {
+        "(not set)".to_string()
+    } else if token.len() <= 12 || !token.is_ascii() {
+        "********".to_string()
+    } else {
+        format!("{}…{}", &token[..4], &token[token.len() - 4..])
+    }
+}
+
+fn mask_tokens(value: &mut serde_json::Value) {
+    if let Some(token) = value.get_mut("github_token") {
+        *token = mask_token(token.as_str().unwrap_or("")).into();
+    }
+    if let Some(serde_json::Value::Object(profiles)) = value.get_mut("profiles") {
+        profiles.values_mut().for_each(mask_tokens);
+    }
+}
+
+/// The config file with one profile taken out for editing.
+pub struct ProfileEdit {
+    file: Config,
+    /// Profile being edited; `None` for the top-level default.
+    pub name: Option<String>,
+}
+
+/// Take the profile selected with `--profile` (or the top-level default)
+/// out of the config file so it can be edited and saved back.
+pub fn edit_profile() -> Result<(ProfileEdit, Config), String> {
+    let mut file = read_config()?.unwrap_or_else(|| Config {
+        default_branch: Some("main".to_string()),
+        ..Config::default()
+    });
+
+    let name = requested_profile().filter(|name| name != DEFAULT_PROFILE);
+    let config = match &name {
+        Some(name) => file.profiles.remove(name).unwrap_or_default(),
+        None => std::mem::take(&mut file),
+    };
+    Ok((ProfileEdit { file, name }, config))
+}
+
+impl ProfileEdit {
+    pub fn profile_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// Put the edited profile back and write the config file.
+    pub fn save(mut self, config: Config) -> Result<(), String> {
+        match self.name {
+            Some(name) => {
+                self.file.profiles.insert(name, config);
+                save_config(&self.file)
+            }
+            None => save_config(&config),
+        }
+    }
+}
+
+pub fn save_config(config: &Config) -> Result<(), String> {
+    let config_path = get_config_path()?;
+    if dry_run::is_dry_run() {
+        // the planned write is printed, so keep the tokens out of it
+        let mut value = serde_json::to_value(config).map_err(|e| e.to_string())?;
+        mask_tokens(&mut value);
+        let config_str = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+        return dry_run::write(&config_path, config_str).map_err(|e| e.to_string());
+    }
+    let config_str = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    write_private(&config_path, &config_str)
+}
+
+// create or truncate the file so that only the owner can read it
+#[cfg(unix)]
+fn write_private(path: &std::path::Path, contents: &str) -> Result<(), String> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    // the mode only applies to new files, so fix up an existing one too
+    restrict_permissions(path, 0o600)?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &std::path::Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| e.to_string())
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &std::path::Path, mode: u32) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|e| e.to_string())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &std::path::Path, _mode: u32) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn warn_if_readable_by_others(path: &std::path::Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Ok(metadata) = fs::metadata(path) {
+        let mode = metadata.permissions().mode();
+        if mode & 0o077 != 0 {
+            say!(
+                "{} {} is accessible by other users (mode {:o}). Run 'chmod 600 {}' or 'git_mate config' to fix it.",
+                "Warning:".yellow(),
+                path.display(),
+                mode & 0o777,
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_readable_by_others(_path: &std::path::Path) {}
+
+// derive the web URL from an API base URL:
+// - https://api.github.com -> https://github.com
+// - https://ghe.example.com/api/v3 -> https://ghe.example.com
+// anything else (e.g. a local mock server) is used as is
+pub fn web_url_from_api_url(api_url: &str) -> String {
+    let api_url = api_url.trim_end_matches('/');
+    if let Some(rest) = api_url.strip_prefix("https://api.") {
+        format!("https://{}", rest)
+    } else if let Some(base) = api_url.strip_suffix("/api/v3") {
+        base.to_string()
+    } else {
+        api_url.to_string()
+    }
+}
+
+// host (and port) part of a URL, e.g. "github.com" for "https://github.com/foo"
+pub fn url_host(url: &str) -> &str {
+    let without_scheme = url.split("://").nth(1).unwrap_or(url);
+    without_scheme.split('/').next().unwrap_or(without_scheme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::parse_remote_url;
+
+    // a default github.com account "alice" with `profiles`, each given as
+    // (name, API URL, username, owners)
+    fn config(profiles: &[(&str, &str, &str, &[&str])]) -> Config {
+        let mut config = Config {
+            username: "alice".to_string(),
+            ..Config::default()
+        };
+        for (name, api_url, username, owners) in profiles {
+            config.profiles.insert(
+                name.to_string(),
+                Config {
+                    username: username.to_string(),
+                    api_url: Some(api_url.to_string()),
+                    owners: owners.iter().map(|owner| owner.to_string()).collect(),
+                    ..Config::default()
+                },
+            );
+        }
+        config
+    }
+
+    fn profile_for(config: &Config, url: &str) -> Option<String> {
+        config.profile_for_remote(&parse_remote_url(url).unwrap())
+    }
+
+    #[test]
+    fn host_and_owner_match_first() {
+        let config = config(&[
+            ("work", "https://api.github.com", "alice-work", &["acme"]),
+            ("ghe", "https://ghe.corp.com/api/v3", "alice", &["acme"]),
+        ]);
+        assert_eq!(
+            profile_for(&config, "git@github.com:acme/demo.git"),
+            Some("work".to_string())
+        );
+        assert_eq!(
+            profile_for(&config, "https://ghe.corp.com/acme/demo.git"),
+            Some("ghe".to_string())
+        );
+        assert_eq!(profile_for(&config, "git@github.com:alice/demo.git"), None);
+    }
+
+    #[test]
+    fn owner_matches_through_ssh_host_aliases() {
+        let config = config(&[("work", "https://api.github.com", "alice-work", &["acme"])]);
+        assert_eq!(
+            profile_for(&config, "github-work:acme/demo.git"),
+            Some("work".to_string())
+        );
+        assert_eq!(
+            profile_for(&config, "ssh://git@github-work:2222/alice-work/demo.git"),
+            Some("work".to_string())
+        );
+    }
+
+    #[test]
+    fn only_profile_on_a_host() {
+        let config = config(&[("ghe", "https://ghe.corp.com/api/v3", "alice", &[])]);
+        assert_eq!(
+            profile_for(&config, "ssh://git@ghe.corp.com:2222/team/demo.git"),
+            Some("ghe".to_string())
+        );
+    }
+
+    #[test]
+    fn several_profiles_on_a_host_fall_back_to_the_default() {
+        let config = config(&[
+            (
+                "ghe-a",
+                "https://ghe.corp.com/api/v3",
+                "alice-a",
+                &["team-a"],
+            ),
+            (
+                "ghe-b",
+                "https://ghe.corp.com/api/v3",
+                "alice-b",
+                &["team-b"],
+            ),
+        ]);
+        assert_eq!(
+            profile_for(&config, "https://ghe.corp.com/team-c/demo.git"),
+            None
+        );
+        assert_eq!(
+            profile_for(&config, "https://ghe.corp.com/team-b/demo.git"),
+            Some("ghe-b".to_string())
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::Path;
+
+/// A project language recognized by the files at the project root.
+#[derive(Debug)]
+pub struct Language {
+    pub name: &'static str,
+    /// File names, or `*.ext` patterns, that mark a project of this language.
+    markers: &'static [&'static str],
+    /// Name of the matching template in GitHub's .gitignore collection.
+    pub gitignore: &'static str,
+}
+
+// checked in order, so languages whose projects often carry a package.json
+// or similar for tooling come before the ones that only have that file
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "Rust",
+        markers: &["Cargo.toml"],
+        gitignore: "Rust",
+    },
+    Language {
+        name: "Go",
+        markers: &["go.mod"],
+        gitignore: "Go",
+    },
+    Language {
+        name: "Java",
+        markers: &["pom.xml", "build.gradle", "build.gradle.kts"],
+        gitignore: "Java",
+    },
+    Language {
+        name: "Python",
+        markers: &[
+            "pyproject.toml",
+            "setup.py",
+            "setup.cfg",
+            "requirements.txt",
+            "Pipfile",
+        ],
+        gitignore: "Python",
+    },
+    Language {
+        name: "Ruby",
+        markers: &["Gemfile", "*.gemspec"],
+        gitignore: "Ruby",
+    },
+    Language {
+        name: "Elixir",
+        markers: &["mix.exs"],
+        gitignore: "Elixir",
+    },
+    Language {
+        name: "PHP",
+        markers: &["composer.json"],
+        gitignore: "Composer",
+    },
+    Language {
+        name: "Dart",
+        markers: &["pubspec.yaml"],
+        gitignore: "Dart",
+    },
+    Language {
+        name: "Swift",
+        markers: &["Package.swift"],
+        gitignore: "Swift",
+    },
+    Language {
+        name: "Haskell",
+        markers: &["stack.yaml", "*.cabal"],
+        gitignore: "Haskell",
+    },
+    Language {
+        name: "C#",
+        markers: &["*.sln", "*.csproj"],
+        gitignore: "VisualStudio",
+    },
+    Language {
+        name: "C++",
+        markers: &["CMakeLists.txt"],
+        gitignore: "C++",
+    },
+    Language {
+        name: "Node.js",
+        markers: &["package.json"],
+        gitignore: "Node",
+    },
+];
+
+/// The language of the project in `dir`, with the marker file that gave it away.
+pub fn detect_language(dir: &Path) -> Option<(&'static Language, String)> {
+    let files: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+
+    LANGUAGES.iter().find_map(|language| {
+        language.markers.iter().find_map(|marker| {
+            files
+                .iter()
+                .find(|file| matches_marker(file, marker))
+                .map(|file| (language, file.clone()))
+        })
+    })
+}
+
+fn matches_marker(file: &str, marker: &str) -> bool {
+    match marker.strip_prefix('*') {
+        Some(extension) => file.len() > extension.len() && file.ends_with(extension),
+        None => file == marker,
+    }
+}
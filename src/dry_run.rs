@@ -0,0 +1,105 @@
+use colored::*;
+use serde_json::{json, Value};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+static PLANNED: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+/// With `--dry-run`, git commands that change something, file writes and
+/// GitHub requests that change something are recorded and printed instead
+/// of being executed. Queries still run so the plan reflects reality.
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Actions recorded so far, for the `--json` result.
+pub fn planned() -> Vec<Value> {
+    PLANNED
+        .lock()
+        .map(|planned| planned.clone())
+        .unwrap_or_default()
+}
+
+fn record(action: Value) {
+    if let Ok(mut planned) = PLANNED.lock() {
+        planned.push(action);
+    }
+}
+
+/// Record running `command` with `args`.
+pub fn command(command: &str, args: &[&str]) {
+    let line = std::iter::once(command)
+        .chain(args.iter().copied())
+        .map(|arg| {
+            if arg.contains(' ') || arg.is_empty() {
+                format!("{:?}", arg)
+            } else {
+                arg.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    say!("{} run: {}", "[dry-run]".magenta(), line);
+    record(json!({ "action": "run", "command": line }));
+}
+
+/// Record a GitHub API request and its payload.
+pub fn request(method: &str, url: &str, body: Option<Value>) {
+    say!("{} {} {}", "[dry-run]".magenta(), method, url);
+    if let Some(body) = &body {
+        let pretty = serde_json::to_string_pretty(body).unwrap_or_else(|_| body.to_string());
+        for line in pretty.lines() {
+            say!("          {}", line);
+        }
+    }
+    record(json!({
+        "action": "request",
+        "method": method,
+        "url": url,
+        "body": body,
+    }));
+}
+
+/// Write `contents` to `path`, or record the write in a dry run.
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    if !is_dry_run() {
+        return fs::write(path, contents);
+    }
+
+    let verb = if path.exists() { "overwrite" } else { "write" };
+    say!(
+        "{} {} {} ({} bytes)",
+        "[dry-run]".magenta(),
+        verb,
+        path.display(),
+        contents.len()
+    );
+    record(json!({
+        "action": verb,
+        "path": path.display().to_string(),
+        "contents": String::from_utf8_lossy(contents),
+    }));
+    Ok(())
+}
+
+/// Remove the file at `path`, or record the removal in a dry run.
+pub fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    if !is_dry_run() {
+        return fs::remove_file(path);
+    }
+
+    say!("{} remove {}", "[dry-run]".magenta(), path.display());
+    record(json!({ "action": "remove", "path": path.display().to_string() }));
+    Ok(())
+}
@@ -0,0 +1,569 @@
+use crate::dry_run;
+use crate::Config;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, USER_AGENT};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+
+/// Errors returned by the GitHub API client.
+#[derive(Debug)]
+pub enum GitHubError {
+    /// The token is missing, invalid or lacks permission (401/403).
+    Auth { status: u16, message: String },
+    /// The requested resource does not exist or is not visible to the token (404).
+    NotFound { message: String },
+    /// GitHub rejected the request payload (422), with the entries of its `errors` array.
+    Validation {
+        message: String,
+        errors: Vec<String>,
+    },
+    /// Any other non-success response.
+    Api { status: u16, message: String },
+    /// The request could not be sent or the connection failed.
+    Network(String),
+    /// The response body was not what we expected.
+    Decode(String),
+}
+
+impl fmt::Display for GitHubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitHubError::Auth { status: 0, message } => write!(
+                f,
+                "authentication failed: {}. Check your token with 'git_mate config'.",
+                message
+            ),
+            GitHubError::Auth { status, message } => write!(
+                f,
+                "authentication failed ({}): {}. Check your token with 'git_mate config'.",
+                status, message
+            ),
+            GitHubError::NotFound { message } => write!(
+                f,
+                "not found (404): {}. Check the name and that your token has access to it.",
+                message
+            ),
+            GitHubError::Validation { message, errors } => {
+                if errors.is_empty() {
+                    write!(f, "validation failed: {}", message)
+                } else {
+                    write!(f, "validation failed: {} ({})", message, errors.join("; "))
+                }
+            }
+            GitHubError::Api { status: 0, message } => write!(f, "GitHub error: {}", message),
+            GitHubError::Api { status, message } => {
+                write!(f, "GitHub API error ({}): {}", status, message)
+            }
+            GitHubError::Network(e) => write!(f, "network error: {}", e),
+            GitHubError::Decode(e) => write!(f, "unexpected response from GitHub: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GitHubError {}
+
+/// The account a token belongs to, as reported by `GET /user`.
+#[derive(Debug, Deserialize)]
+pub struct AuthenticatedUser {
+    pub login: String,
+    pub name: Option<String>,
+    /// Scopes from the `X-OAuth-Scopes` header. `None` for tokens that
+    /// don't report scopes, such as fine-grained personal access tokens.
+    #[serde(skip)]
+    pub scopes: Option<Vec<String>>,
+}
+
+/// An organization the authenticated user belongs to.
+#[derive(Debug, Deserialize)]
+pub struct Organization {
+    pub login: String,
+}
+
+/// A license from GitHub's license collection.
+#[derive(Debug, Deserialize)]
+pub struct License {
+    pub key: String,
+    pub name: String,
+    #[serde(default)]
+    pub spdx_id: Option<String>,
+}
+
+// OAuth scopes and the scopes they include, see
+// https://docs.github.com/en/apps/oauth-apps/building-oauth-apps/scopes-for-oauth-apps
+const SCOPE_CHILDREN: &[(&str, &[&str])] = &[
+    (
+        "repo",
+        &[
+            "repo:status",
+            "repo_deployment",
+            "public_repo",
+            "repo:invite",
+            "security_events",
+        ],
+    ),
+    ("admin:repo_hook", &["write:repo_hook"]),
+    ("write:repo_hook", &["read:repo_hook"]),
+    ("admin:org", &["write:org", "manage_runners:org"]),
+    ("write:org", &["read:org"]),
+    ("admin:public_key", &["write:public_key"]),
+    ("write:public_key", &["read:public_key"]),
+    ("admin:gpg_key", &["write:gpg_key"]),
+    ("write:gpg_key", &["read:gpg_key"]),
+    ("admin:ssh_signing_key", &["write:ssh_signing_key"]),
+    ("write:ssh_signing_key", &["read:ssh_signing_key"]),
+    ("user", &["read:user", "user:email", "user:follow"]),
+    ("write:packages", &["read:packages"]),
+    ("project", &["read:project"]),
+    ("codespace", &["codespace:secrets"]),
+    (
+        "admin:enterprise",
+        &[
+            "manage_runners:enterprise",
+            "manage_billing:enterprise",
+            "read:enterprise",
+        ],
+    ),
+];
+
+impl AuthenticatedUser {
+    /// Whether `scope` was granted, counting parent scopes (`repo` covers
+    /// `public_repo`, `admin:org` covers `read:org`). Tokens that don't
+    /// report scopes are assumed to have them all.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        match &self.scopes {
+            Some(scopes) => scopes.iter().any(|granted| includes_scope(granted, scope)),
+            None => true,
+        }
+    }
+}
+
+fn includes_scope(granted: &str, scope: &str) -> bool {
+    granted == scope
+        || SCOPE_CHILDREN
+            .iter()
+            .filter(|(parent, _)| *parent == granted)
+            .flat_map(|(_, children)| children.iter())
+            .any(|child| includes_scope(child, scope))
+}
+
+/// A run of a GitHub Actions workflow.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WorkflowRun {
+    pub id: u64,
+    pub run_number: u64,
+    /// Name of the workflow.
+    pub name: Option<String>,
+    /// Commit message or pull request title the run is for.
+    pub display_title: Option<String>,
+    pub event: String,
+    pub head_branch: Option<String>,
+    /// "queued", "in_progress", "completed", ...
+    pub status: Option<String>,
+    /// "success", "failure", "cancelled", ... once completed.
+    pub conclusion: Option<String>,
+    pub html_url: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRuns {
+    workflow_runs: Vec<WorkflowRun>,
+}
+
+/// A job of a workflow run, with its steps.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Step {
+    pub number: u64,
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub started_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Jobs {
+    jobs: Vec<Job>,
+}
+
+/// Thin wrapper around a blocking reqwest client that knows how to talk to
+/// the configured GitHub API.
+pub struct GitHubClient {
+    client: Client,
+    api_url: String,
+    web_url: String,
+}
+
+impl GitHubClient {
+    pub fn new(config: &Config) -> Result<Self, GitHubError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/vnd.github.v3+json"),
+        );
+        headers.insert(USER_AGENT, HeaderValue::from_static("Git-Mate-CLI"));
+        if !config.token().is_empty() {
+            let mut auth =
+                HeaderValue::from_str(&format!("token {}", config.token())).map_err(|_| {
+                    GitHubError::Auth {
+                        status: 0,
+                        message: "token contains invalid characters".to_string(),
+                    }
+                })?;
+            auth.set_sensitive(true);
+            headers.insert(AUTHORIZATION, auth);
+        }
+
+        let client = Client::builder()
+            .default_headers(headers)
+            .build()
+            .map_err(|e| GitHubError::Network(e.to_string()))?;
+
+        Ok(GitHubClient {
+            client,
+            api_url: config.api_url(),
+            web_url: config.web_url(),
+        })
+    }
+
+    pub fn web_url(&self) -> &str {
+        &self.web_url
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, GitHubError> {
+        let res = self.send(self.client.get(self.url(path)))?;
+        decode(res)
+    }
+
+    pub fn post<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<T, GitHubError> {
+        if dry_run::is_dry_run() {
+            return self.plan("POST", path, body);
+        }
+        let res = self.send(self.client.post(self.url(path)).json(body))?;
+        decode(res)
+    }
+
+    pub fn patch<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<T, GitHubError> {
+        if dry_run::is_dry_run() {
+            return self.plan("PATCH", path, body);
+        }
+        let res = self.send(self.client.patch(self.url(path)).json(body))?;
+        decode(res)
+    }
+
+    pub fn put<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<T, GitHubError> {
+        if dry_run::is_dry_run() {
+            return self.plan("PUT", path, body);
+        }
+        let res = self.send(self.client.put(self.url(path)).json(body))?;
+        decode(res)
+    }
+
+    pub fn delete(&self, path: &str) -> Result<(), GitHubError> {
+        if dry_run::is_dry_run() {
+            dry_run::request("DELETE", &self.url(path), None);
+            return Ok(());
+        }
+        self.send(self.client.delete(self.url(path)))?;
+        Ok(())
+    }
+
+    /// Verify the token and return the account it belongs to.
+    pub fn current_user(&self) -> Result<AuthenticatedUser, GitHubError> {
+        let res = self.send(self.client.get(self.url("/user")))?;
+        let scopes = res
+            .headers()
+            .get("x-oauth-scopes")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| {
+                v.split(',')
+                    .map(|scope| scope.trim().to_string())
+                    .filter(|scope| !scope.is_empty())
+                    .collect()
+            });
+        let mut user: AuthenticatedUser = decode(res)?;
+        user.scopes = scopes;
+        Ok(user)
+    }
+
+    /// Organizations the user belongs to; needs the `read:org` scope for
+    /// private memberships.
+    pub fn organizations(&self) -> Result<Vec<Organization>, GitHubError> {
+        self.get("/user/orgs?per_page=100")
+    }
+
+    /// Commonly used licenses that can be added to a repository.
+    pub fn licenses(&self) -> Result<Vec<License>, GitHubError> {
+        self.get("/licenses")
+    }
+
+    /// Recent workflow runs of `repo`, newest first, optionally only those
+    /// of one workflow (file name or ID) or branch.
+    pub fn workflow_runs(
+        &self,
+        repo: &str,
+        workflow: Option<&str>,
+        branch: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<WorkflowRun>, GitHubError> {
+        let path = match workflow {
+            Some(workflow) => format!("/repos/{}/actions/workflows/{}/runs", repo, workflow),
+            None => format!("/repos/{}/actions/runs", repo),
+        };
+        // branch names may contain characters such as & and # that have to
+        // be encoded
+        let mut query = vec![("per_page", limit.to_string())];
+        if let Some(branch) = branch {
+            query.push(("branch", branch.to_string()));
+        }
+        let res = self.send(self.client.get(self.url(&path)).query(&query))?;
+        let runs: WorkflowRuns = decode(res)?;
+        Ok(runs.workflow_runs)
+    }
+
+    pub fn workflow_run(&self, repo: &str, id: u64) -> Result<WorkflowRun, GitHubError> {
+        self.get(&format!("/repos/{}/actions/runs/{}", repo, id))
+    }
+
+    /// Jobs of the latest attempt of a run.
+    pub fn run_jobs(&self, repo: &str, id: u64) -> Result<Vec<Job>, GitHubError> {
+        let jobs: Jobs = self.get(&format!(
+            "/repos/{}/actions/runs/{}/jobs?per_page=100",
+            repo, id
+        ))?;
+        Ok(jobs.jobs)
+    }
+
+    /// The zip archive with the logs of a run's latest attempt. GitHub
+    /// redirects to a short-lived download URL, which gets no token.
+    pub fn run_logs(&self, repo: &str, id: u64) -> Result<Vec<u8>, GitHubError> {
+        let path = format!("/repos/{}/actions/runs/{}/logs", repo, id);
+        // archives of long runs take longer than the default 30 seconds
+        let res = self.send(
+            self.client
+                .get(self.url(&path))
+                .timeout(Duration::from_secs(300)),
+        )?;
+        res.bytes()
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| GitHubError::Network(e.to_string()))
+    }
+
+    // record a request instead of sending it; callers get an empty object
+    fn plan<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<T, GitHubError> {
+        dry_run::request(method, &self.url(path), serde_json::to_value(body).ok());
+        serde_json::from_value(Value::Object(Default::default()))
+            .map_err(|e| GitHubError::Decode(e.to_string()))
+    }
+
+    // paths such as "/user/repos" on the configured API; absolute URLs are
+    // not accepted so the token is never sent to another host
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.api_url, path.trim_start_matches('/'))
+    }
+
+    fn send(&self, request: RequestBuilder) -> Result<Response, GitHubError> {
+        let res = request
+            .send()
+            .map_err(|e| GitHubError::Network(e.to_string()))?;
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(error_from_response(res))
+        }
+    }
+}
+
+// endpoints that answer with 204 No Content, such as workflow dispatches,
+// decode as JSON null
+fn decode<T: DeserializeOwned>(res: Response) -> Result<T, GitHubError> {
+    let text = res
+        .text()
+        .map_err(|e| GitHubError::Network(e.to_string()))?;
+    let text = if text.trim().is_empty() {
+        "null"
+    } else {
+        &text
+    };
+    serde_json::from_str(text).map_err(|e| GitHubError::Decode(e.to_string()))
+}
+
+fn error_from_response(res: Response) -> GitHubError {
+    let status = res.status();
+    let rate_limited = res
+        .headers()
+        .get("x-ratelimit-remaining")
+        .and_then(|v| v.to_str().ok())
+        == Some("0");
+    let text = res.text().unwrap_or_default();
+    let body: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+    let message = body["message"]
+        .as_str()
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| {
+            if text.trim().is_empty() {
+                status
+                    .canonical_reason()
+                    .unwrap_or("Unknown error")
+                    .to_string()
+            } else {
+                text.trim().to_string()
+            }
+        });
+
+    match status {
+        StatusCode::FORBIDDEN if rate_limited => GitHubError::Api {
+            status: status.as_u16(),
+            message: format!("{} (rate limit exceeded, try again later)", message),
+        },
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => GitHubError::Auth {
+            status: status.as_u16(),
+            message,
+        },
+        StatusCode::NOT_FOUND => GitHubError::NotFound { message },
+        StatusCode::UNPROCESSABLE_ENTITY => GitHubError::Validation {
+            message,
+            errors: validation_errors(&body),
+        },
+        _ => GitHubError::Api {
+            status: status.as_u16(),
+            message,
+        },
+    }
+}
+
+// GitHub reports validation problems as an `errors` array whose entries are
+// either plain strings or objects with resource/field/code/message keys
+fn validation_errors(body: &Value) -> Vec<String> {
+    let errors = match body["errors"].as_array() {
+        Some(errors) => errors,
+        None => return Vec::new(),
+    };
+
+    errors
+        .iter()
+        .map(|error| {
+            if let Some(text) = error.as_str() {
+                return text.to_string();
+            }
+            if let Some(message) = error["message"].as_str() {
+                return message.to_string();
+            }
+            let resource = error["resource"].as_str().unwrap_or("resource");
+            let field = error["field"].as_str().unwrap_or("");
+            let code = error["code"].as_str().unwrap_or("invalid");
+            let reason = match code {
+                "missing" => "does not exist",
+                "missing_field" => "is required",
+                "invalid" => "is invalid",
+                "already_exists" => "already exists",
+                "unprocessable" => "could not be processed",
+                other => other,
+            };
+            if field.is_empty() {
+                format!("{} {}", resource, reason)
+            } else {
+                format!("{}.{} {}", resource, field, reason)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn user(scopes: Option<&[&str]>) -> AuthenticatedUser {
+        AuthenticatedUser {
+            login: "alice".to_string(),
+            name: None,
+            scopes: scopes.map(|scopes| scopes.iter().map(|s| s.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn repo_covers_public_repo() {
+        let repo = user(Some(&["repo"]));
+        assert!(repo.has_scope("repo"));
+        assert!(repo.has_scope("public_repo"));
+        assert!(repo.has_scope("repo:status"));
+        assert!(!repo.has_scope("workflow"));
+        assert!(!user(Some(&["public_repo"])).has_scope("repo"));
+    }
+
+    #[test]
+    fn parent_scopes_cover_grandchildren() {
+        let admin = user(Some(&["admin:org", "workflow"]));
+        assert!(admin.has_scope("write:org"));
+        assert!(admin.has_scope("read:org"));
+        assert!(admin.has_scope("workflow"));
+        assert!(!admin.has_scope("admin:org_hook"));
+        assert!(!user(Some(&["read:org"])).has_scope("admin:org"));
+    }
+
+    #[test]
+    fn tokens_without_scopes_have_them_all() {
+        assert!(user(None).has_scope("repo"));
+        assert!(!user(Some(&[])).has_scope("repo"));
+    }
+
+    #[test]
+    fn validation_errors_of_every_shape() {
+        let body = json!({
+            "message": "Validation Failed",
+            "errors": [
+                "name is too long",
+                { "resource": "Repository", "code": "custom", "message": "name already exists on this account" },
+                { "resource": "Issue", "field": "title", "code": "missing_field" },
+                { "resource": "Label", "code": "already_exists" },
+                { "resource": "Repository", "field": "topics", "code": "too_many" },
+            ]
+        });
+        assert_eq!(
+            validation_errors(&body),
+            [
+                "name is too long",
+                "name already exists on this account",
+                "Issue.title is required",
+                "Label already exists",
+                "Repository.topics too_many",
+            ]
+        );
+    }
+
+    #[test]
+    fn validation_errors_without_an_errors_array() {
+        assert!(validation_errors(&json!({ "message": "Validation Failed" })).is_empty());
+    }
+}
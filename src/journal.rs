@@ -0,0 +1,137 @@
+use crate::dry_run;
+use crate::repo::{CreatedRepo, RepoInfo};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Kept inside `.git` so it is never committed.
+const JOURNAL_FILE: &str = ".git/git_mate_init.json";
+
+/// Steps of `git_mate init` that change something and are skipped on resume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InitStep {
+    ProjectFiles,
+    CreateRepo,
+    RepoSettings,
+    Remote,
+    MergeHistory,
+    Readme,
+    Workflow,
+    Commit,
+}
+
+/// How `init` changed the `origin` remote.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteChange {
+    Added,
+    Replaced { previous_url: String },
+}
+
+/// A file `init` wrote, with its contents from before if it existed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WrittenFile {
+    pub path: String,
+    pub previous: Option<String>,
+}
+
+/// Record of an `init` run: its inputs, the steps completed so far and what
+/// they changed, so a failed run can be resumed or undone.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InitJournal {
+    pub repo_info: RepoInfo,
+    pub gitignore: Option<String>,
+    pub license: Option<String>,
+    /// Copyright holder from `--holder`.
+    pub holder: Option<String>,
+    pub workflow: Option<String>,
+    /// Remote URL protocol from `--protocol`.
+    pub protocol: Option<String>,
+    /// Whether `repo` already existed rather than being created by `init`.
+    pub linked: bool,
+    pub repo: Option<CreatedRepo>,
+    pub remote: Option<RemoteChange>,
+    pub merged: bool,
+    pub files: Vec<WrittenFile>,
+    pub completed: Vec<InitStep>,
+}
+
+impl InitJournal {
+    pub fn new(repo_info: RepoInfo, existing: Option<CreatedRepo>) -> Self {
+        InitJournal {
+            repo_info,
+            gitignore: None,
+            license: None,
+            holder: None,
+            workflow: None,
+            protocol: None,
+            linked: existing.is_some(),
+            repo: existing,
+            remote: None,
+            merged: false,
+            files: Vec::new(),
+            completed: Vec::new(),
+        }
+    }
+
+    /// The journal of an unfinished `init` in this repository, if any.
+    pub fn load() -> Result<Option<Self>, String> {
+        match fs::read_to_string(JOURNAL_FILE) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map(Some)
+                .map_err(|e| format!("Invalid {}: {}", JOURNAL_FILE, e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", JOURNAL_FILE, e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if dry_run::is_dry_run() {
+            return Ok(());
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(JOURNAL_FILE, contents)
+            .map_err(|e| format!("Failed to write {}: {}", JOURNAL_FILE, e))
+    }
+
+    pub fn remove() {
+        if dry_run::is_dry_run() {
+            return;
+        }
+        let _ = fs::remove_file(JOURNAL_FILE);
+    }
+
+    pub fn is_done(&self, step: InitStep) -> bool {
+        self.completed.contains(&step)
+    }
+
+    pub fn complete(&mut self, step: InitStep) -> Result<(), String> {
+        if !self.is_done(step) {
+            self.completed.push(step);
+        }
+        self.save()
+    }
+
+    /// Remember `path` before it is written.
+    pub fn record_file(&mut self, path: &str) {
+        let previous = if Path::new(path).exists() {
+            fs::read_to_string(path).ok()
+        } else {
+            None
+        };
+        self.record_written(path, previous);
+    }
+
+    /// Remember that `path` was written over `previous`, keeping the
+    /// contents from the first time it was seen.
+    pub fn record_written(&mut self, path: &str, previous: Option<String>) {
+        if !self.files.iter().any(|file| file.path == path) {
+            self.files.push(WrittenFile {
+                path: path.to_string(),
+                previous,
+            });
+        }
+    }
+}
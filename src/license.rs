@@ -0,0 +1,312 @@
+use crate::config::Config;
+use crate::git::get_config;
+use crate::github::GitHubClient;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Replace the `[year]` and `[fullname]` placeholders GitHub leaves in
+/// license bodies. Placeholders in a license's how-to-apply appendix, such
+/// as Apache's `[yyyy]`, are instructions and stay as they are.
+pub fn fill_placeholders(body: &str, year: i64, holder: &str) -> String {
+    body.replace("[year]", &year.to_string())
+        .replace("[fullname]", holder)
+}
+
+/// The copyright holder: `holder` if given, then the configured holder,
+/// the GitHub profile name, `git config user.name` and the username.
+pub fn copyright_holder(config: &Config, holder: Option<&str>) -> String {
+    let non_empty = |name: String| Some(name.trim().to_string()).filter(|name| !name.is_empty());
+
+    holder
+        .map(|holder| holder.to_string())
+        .or_else(|| config.copyright_holder.clone())
+        .and_then(non_empty)
+        .or_else(|| {
+            if config.token().is_empty() {
+                return None;
+            }
+            GitHubClient::new(config)
+                .and_then(|client| client.current_user())
+                .ok()
+                .and_then(|user| user.name)
+                .and_then(non_empty)
+        })
+        .or_else(|| get_config("user.name").and_then(non_empty))
+        .unwrap_or_else(|| config.username.clone())
+}
+
+/// The current year in UTC.
+pub fn current_year() -> i64 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() / 86_400)
+        .unwrap_or(0) as i64;
+    year_from_days(days)
+}
+
+// civil year of a day counted from 1970-01-01
+fn year_from_days(days: i64) -> i64 {
+    // civil date from days since 1970-01-01, keeping only the year; eras
+    // are 400 years and years start in March so leap days come last
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let march_based_month = (5 * day_of_year + 2) / 153;
+    let year = era * 400 + year_of_era;
+    if march_based_month >= 10 {
+        year + 1
+    } else {
+        year
+    }
+}
+
+/// How comments are written in a kind of source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentStyle {
+    /// Each line starts with the given prefix, e.g. `//` or `#`.
+    Line(&'static str),
+    /// Each line is wrapped, e.g. `<!-- ... -->` or `/* ... */`.
+    Block(&'static str, &'static str),
+}
+
+// extensions, and whole file names for files without one
+const COMMENT_STYLES: &[(&[&str], CommentStyle)] = &[
+    (
+        &[
+            "rs", "c", "h", "cc", "cpp", "cxx", "hpp", "hh", "go", "java", "kt", "kts", "scala",
+            "groovy", "gradle", "swift", "dart", "cs", "fs", "js", "jsx", "mjs", "cjs", "ts",
+            "tsx", "php", "proto", "zig", "scss", "less",
+        ],
+        CommentStyle::Line("//"),
+    ),
+    (
+        &[
+            "py",
+            "rb",
+            "sh",
+            "bash",
+            "zsh",
+            "fish",
+            "pl",
+            "pm",
+            "r",
+            "ex",
+            "exs",
+            "yml",
+            "yaml",
+            "toml",
+            "tf",
+            "cmake",
+            "ps1",
+            "nix",
+            "Dockerfile",
+            "Makefile",
+            "Gemfile",
+            "Rakefile",
+        ],
+        CommentStyle::Line("#"),
+    ),
+    (
+        &["sql", "lua", "hs", "elm", "ada"],
+        CommentStyle::Line("--"),
+    ),
+    (&["erl", "hrl", "tex"], CommentStyle::Line("%")),
+    (
+        &["el", "clj", "cljs", "lisp", "scm"],
+        CommentStyle::Line(";;"),
+    ),
+    (&["css"], CommentStyle::Block("/*", "*/")),
+    (
+        &["html", "htm", "xml", "xhtml", "vue", "svelte"],
+        CommentStyle::Block("<!--", "-->"),
+    ),
+];
+
+/// The comment style for `path`, or `None` for files that don't get headers.
+pub fn comment_style(path: &str) -> Option<CommentStyle> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let key = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => extension,
+        _ => name,
+    };
+    COMMENT_STYLES
+        .iter()
+        .find(|(keys, _)| keys.contains(&key))
+        .map(|(_, style)| *style)
+}
+
+/// Whether `contents` already starts with an SPDX license header.
+pub fn has_header(contents: &str) -> bool {
+    contents
+        .lines()
+        .take(10)
+        .any(|line| line.contains("SPDX-License-Identifier:"))
+}
+
+/// The license and copyright header for a file, one comment per line.
+pub fn header(style: CommentStyle, spdx_id: &str, year: i64, holder: &str) -> String {
+    [
+        format!("SPDX-License-Identifier: {}", spdx_id),
+        format!("Copyright (c) {} {}", year, holder),
+    ]
+    .iter()
+    .map(|line| match style {
+        CommentStyle::Line(prefix) => format!("{} {}\n", prefix, line),
+        CommentStyle::Block(open, close) => format!("{} {} {}\n", open, line, close),
+    })
+    .collect()
+}
+
+/// Put `header` at the top of `contents`, after a byte order mark and a
+/// first line that has to stay first: a shebang, an XML declaration,
+/// `<?php` or a doctype.
+pub fn insert_header(contents: &str, header: &str) -> String {
+    let (bom, contents) = match contents.strip_prefix('\u{feff}') {
+        Some(rest) => ("\u{feff}", rest),
+        None => ("", contents),
+    };
+    let first_line_len = contents.find('\n').map_or(contents.len(), |end| end + 1);
+    let first_line = contents[..first_line_len].to_lowercase();
+    // `#![...]` is a Rust inner attribute, not a shebang
+    let shebang = first_line.starts_with("#!") && !first_line.starts_with("#![");
+    let keep_first = shebang
+        || ["<?xml", "<?php", "<!doctype"]
+            .iter()
+            .any(|start| first_line.starts_with(start));
+
+    if keep_first {
+        let (first, rest) = contents.split_at(first_line_len);
+        let newline = if first.ends_with('\n') { "" } else { "\n" };
+        format!("{}{}{}{}\n{}", bom, first, newline, header, rest)
+    } else if contents.is_empty() {
+        format!("{}{}", bom, header)
+    } else {
+        format!("{}{}\n{}", bom, header, contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn year_from_days_since_the_epoch() {
+        assert_eq!(year_from_days(0), 1970); // 1970-01-01
+        assert_eq!(year_from_days(-1), 1969); // 1969-12-31
+        assert_eq!(year_from_days(11_017), 2000); // 2000-03-01
+        assert_eq!(year_from_days(19_782), 2024); // 2024-02-29
+        assert_eq!(year_from_days(20_088), 2024); // 2024-12-31
+        assert_eq!(year_from_days(20_089), 2025); // 2025-01-01
+    }
+
+    #[test]
+    fn fill_placeholders_replaces_year_and_fullname() {
+        assert_eq!(
+            fill_placeholders("Copyright (c) [year] [fullname]\n", 2024, "Jane Doe"),
+            "Copyright (c) 2024 Jane Doe\n"
+        );
+        // Apache's appendix placeholders are instructions, not ours to fill
+        assert_eq!(
+            fill_placeholders("Copyright [yyyy] [name of copyright owner]", 2024, "Jane"),
+            "Copyright [yyyy] [name of copyright owner]"
+        );
+    }
+
+    const HEADER: &str = "// SPDX-License-Identifier: MIT\n// Copyright (c) 2024 Jane\n";
+
+    #[test]
+    fn comment_style_by_extension_or_file_name() {
+        assert_eq!(comment_style("src/main.rs"), Some(CommentStyle::Line("//")));
+        assert_eq!(comment_style("build.sh"), Some(CommentStyle::Line("#")));
+        assert_eq!(comment_style("Dockerfile"), Some(CommentStyle::Line("#")));
+        assert_eq!(
+            comment_style("docker/Makefile"),
+            Some(CommentStyle::Line("#"))
+        );
+        assert_eq!(
+            comment_style("index.html"),
+            Some(CommentStyle::Block("<!--", "-->"))
+        );
+        assert_eq!(comment_style("README.md"), None);
+        assert_eq!(comment_style("LICENSE"), None);
+        // a dotfile has no extension
+        assert_eq!(comment_style(".rs"), None);
+    }
+
+    #[test]
+    fn header_per_comment_style() {
+        assert_eq!(
+            header(CommentStyle::Line("//"), "MIT", 2024, "Jane"),
+            HEADER
+        );
+        assert_eq!(
+            header(CommentStyle::Block("/*", "*/"), "MIT", 2024, "Jane"),
+            "/* SPDX-License-Identifier: MIT */\n/* Copyright (c) 2024 Jane */\n"
+        );
+    }
+
+    #[test]
+    fn has_header_looks_at_the_first_lines() {
+        assert!(has_header("#!/bin/sh\n# SPDX-License-Identifier: MIT\n"));
+        assert!(!has_header("fn main() {}\n"));
+        let late = format!("{}// SPDX-License-Identifier: MIT\n", "\n".repeat(10));
+        assert!(!has_header(&late));
+    }
+
+    #[test]
+    fn header_goes_first() {
+        assert_eq!(
+            insert_header("fn main() {}\n", HEADER),
+            format!("{}\nfn main() {{}}\n", HEADER)
+        );
+        assert_eq!(insert_header("", HEADER), HEADER);
+    }
+
+    #[test]
+    fn header_goes_after_a_shebang() {
+        assert_eq!(
+            insert_header("#!/usr/bin/env python\nprint()\n", "# H\n"),
+            "#!/usr/bin/env python\n# H\n\nprint()\n"
+        );
+        assert_eq!(insert_header("#!/bin/sh", "# H\n"), "#!/bin/sh\n# H\n\n");
+    }
+
+    #[test]
+    fn header_goes_before_inner_attributes() {
+        assert_eq!(
+            insert_header("#![deny(warnings)]\nfn main() {}\n", HEADER),
+            format!("{}\n#![deny(warnings)]\nfn main() {{}}\n", HEADER)
+        );
+    }
+
+    #[test]
+    fn header_goes_after_declarations() {
+        assert_eq!(
+            insert_header("<?php\necho 1;\n", HEADER),
+            format!("<?php\n{}\necho 1;\n", HEADER)
+        );
+        assert_eq!(
+            insert_header("<?xml version=\"1.0\"?>\n<a/>\n", "<!-- H -->\n"),
+            "<?xml version=\"1.0\"?>\n<!-- H -->\n\n<a/>\n"
+        );
+        assert_eq!(
+            insert_header("<!DOCTYPE html>\n<html>\n", "<!-- H -->\n"),
+            "<!DOCTYPE html>\n<!-- H -->\n\n<html>\n"
+        );
+    }
+
+    #[test]
+    fn byte_order_mark_stays_first() {
+        assert_eq!(
+            insert_header("\u{feff}print()\n", "# H\n"),
+            "\u{feff}# H\n\nprint()\n"
+        );
+        assert_eq!(
+            insert_header("\u{feff}#!/bin/sh\necho\n", "# H\n"),
+            "\u{feff}#!/bin/sh\n# H\n\necho\n"
+        );
+        assert_eq!(insert_header("\u{feff}", "# H\n"), "\u{feff}# H\n");
+    }
+}
@@ -0,0 +1,333 @@
+use crate::github::{Job, Step};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+/// The log of one job of a workflow run, split by step.
+#[derive(Debug, Serialize)]
+pub struct JobLog {
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub steps: Vec<StepLog>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepLog {
+    pub number: u64,
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub lines: Vec<String>,
+}
+
+impl StepLog {
+    pub fn failed(&self) -> bool {
+        matches!(self.conclusion.as_deref(), Some("failure" | "timed_out"))
+    }
+}
+
+/// The files in a run's log archive, by path.
+pub fn unzip(archive: &[u8]) -> Result<BTreeMap<String, String>, String> {
+    let mut archive = ZipArchive::new(Cursor::new(archive)).map_err(|e| e.to_string())?;
+    let mut files = BTreeMap::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(|e| e.to_string())?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+        files.insert(
+            file.name().to_string(),
+            String::from_utf8_lossy(&bytes).into_owned(),
+        );
+    }
+    Ok(files)
+}
+
+/// Match the archive's files to `jobs` and their steps.
+///
+/// The archive has a `<n>_<job>.txt` file per job and, for most runs, a
+/// `<job>/<number>_<step>.txt` file per step. When the step files are
+/// missing the job log is split by the time each step started.
+pub fn job_logs(files: &BTreeMap<String, String>, jobs: Vec<Job>) -> Vec<JobLog> {
+    jobs.into_iter()
+        .map(|job| {
+            let dir = file_name(&job.name);
+            let mut steps: Vec<StepLog> = job
+                .steps
+                .iter()
+                .map(|step| StepLog {
+                    number: step.number,
+                    name: step.name.clone(),
+                    status: step.status.clone(),
+                    conclusion: step.conclusion.clone(),
+                    lines: step_file(files, &dir, step.number)
+                        .map(lines)
+                        .unwrap_or_default(),
+                })
+                .collect();
+
+            if steps.iter().all(|step| step.lines.is_empty()) {
+                if let Some(log) = job_file(files, &dir) {
+                    split_by_time(lines(log), &job.steps, &mut steps);
+                }
+            }
+
+            JobLog {
+                name: job.name,
+                status: job.status,
+                conclusion: job.conclusion,
+                steps,
+            }
+        })
+        .collect()
+}
+
+// GitHub drops characters that can't be in a path from job names and cuts
+// them at 90 characters
+fn file_name(job: &str) -> String {
+    job.chars()
+        .filter(|c| !matches!(c, '/' | ':'))
+        .take(90)
+        .collect()
+}
+
+fn step_file<'a>(files: &'a BTreeMap<String, String>, dir: &str, number: u64) -> Option<&'a str> {
+    files.iter().find_map(|(path, log)| {
+        let (prefix, _) = path.strip_prefix(dir)?.strip_prefix('/')?.split_once('_')?;
+        (prefix == number.to_string()).then_some(log.as_str())
+    })
+}
+
+fn job_file<'a>(files: &'a BTreeMap<String, String>, dir: &str) -> Option<&'a str> {
+    let name = format!("{}.txt", dir);
+    files.iter().find_map(|(path, log)| {
+        let (prefix, rest) = path.split_once('_')?;
+        let numbered = !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_digit());
+        (numbered && rest == name).then_some(log.as_str())
+    })
+}
+
+fn lines(log: &str) -> Vec<String> {
+    log.trim_start_matches('\u{feff}')
+        .lines()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+// log lines start with a timestamp such as "2024-05-01T12:00:00.1234567Z";
+// each line goes to the last step that started at or before it, compared
+// to the second since the API doesn't give step times any finer
+fn split_by_time(lines: Vec<String>, steps: &[Step], logs: &mut [StepLog]) {
+    let starts: Vec<Option<&str>> = steps
+        .iter()
+        .map(|step| match step.conclusion.as_deref() {
+            Some("skipped") => None,
+            _ => step.started_at.as_deref().and_then(second),
+        })
+        .collect();
+
+    let mut current = 0;
+    for line in lines {
+        if let Some(at) = second(&line) {
+            if let Some(index) = starts
+                .iter()
+                .rposition(|start| start.is_some_and(|start| start <= at))
+            {
+                current = current.max(index);
+            }
+        }
+        if let Some(step) = logs.get_mut(current) {
+            step.lines.push(line);
+        }
+    }
+}
+
+fn second(timestamp: &str) -> Option<&str> {
+    timestamp
+        .get(..19)
+        .filter(|second| second.as_bytes()[10] == b'T')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    fn archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, contents) in files {
+            zip.start_file(*path, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn job(name: &str, steps: &[(u64, &str, &str, &str)]) -> Job {
+        let steps: Vec<_> = steps
+            .iter()
+            .map(|(number, name, conclusion, started_at)| {
+                json!({
+                    "number": number,
+                    "name": name,
+                    "status": "completed",
+                    "conclusion": conclusion,
+                    "started_at": started_at,
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "id": 1,
+            "name": name,
+            "status": "completed",
+            "conclusion": "failure",
+            "steps": steps,
+        }))
+        .unwrap()
+    }
+
+    fn step_lines(logs: &[JobLog]) -> Vec<Vec<Vec<&str>>> {
+        logs.iter()
+            .map(|job| {
+                job.steps
+                    .iter()
+                    .map(|step| step.lines.iter().map(|line| line.as_str()).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn steps_from_step_files() {
+        let files = unzip(&archive(&[
+            (
+                "0_build.txt",
+                "2024-05-01T12:00:00.1Z setup\n2024-05-01T12:00:05.1Z compile\n",
+            ),
+            ("build/", ""),
+            (
+                "build/1_Set up job.txt",
+                "\u{feff}2024-05-01T12:00:00.1Z setup\r\n",
+            ),
+            (
+                "build/2_Run cargo test.txt",
+                "2024-05-01T12:00:05.1Z test a ... FAILED\n##[error]exit code 101\n",
+            ),
+        ]))
+        .unwrap();
+        let jobs = vec![job(
+            "build",
+            &[
+                (1, "Set up job", "success", "2024-05-01T12:00:00Z"),
+                (2, "Run cargo test", "failure", "2024-05-01T12:00:04Z"),
+            ],
+        )];
+
+        let logs = job_logs(&files, jobs);
+        assert_eq!(
+            step_lines(&logs),
+            [[
+                vec!["2024-05-01T12:00:00.1Z setup"],
+                vec![
+                    "2024-05-01T12:00:05.1Z test a ... FAILED",
+                    "##[error]exit code 101"
+                ],
+            ]]
+        );
+        assert!(!logs[0].steps[0].failed());
+        assert!(logs[0].steps[1].failed());
+    }
+
+    #[test]
+    fn steps_split_from_the_job_log_by_start_time() {
+        let files = unzip(&archive(&[(
+            "0_build.txt",
+            "2024-05-01T12:00:00.5Z runner\n\
+             2024-05-01T12:00:03.0Z checkout\n\
+             continued without a timestamp\n\
+             2024-05-01T12:00:09.2Z test\n\
+             2024-05-01T12:00:09.9Z ##[error]failed\n",
+        )]))
+        .unwrap();
+        let jobs = vec![job(
+            "build",
+            &[
+                (1, "Set up job", "success", "2024-05-01T12:00:01Z"),
+                (2, "Checkout", "success", "2024-05-01T12:00:02Z"),
+                (3, "Lint", "skipped", "2024-05-01T12:00:09Z"),
+                (4, "Test", "failure", "2024-05-01T12:00:09Z"),
+            ],
+        )];
+
+        assert_eq!(
+            step_lines(&job_logs(&files, jobs)),
+            [[
+                // lines from before the first step start belong to it
+                vec!["2024-05-01T12:00:00.5Z runner"],
+                vec![
+                    "2024-05-01T12:00:03.0Z checkout",
+                    "continued without a timestamp"
+                ],
+                vec![],
+                vec![
+                    "2024-05-01T12:00:09.2Z test",
+                    "2024-05-01T12:00:09.9Z ##[error]failed"
+                ],
+            ]]
+        );
+    }
+
+    #[test]
+    fn job_names_are_matched_as_file_names() {
+        let files = unzip(&archive(&[
+            ("0_test  unit linux.txt", "job log\n"),
+            ("test  unit linux/1_Run tests.txt", "step log\n"),
+            ("1_lint  rust.txt", "2024-05-01T12:00:00.0Z lint log\n"),
+            ("2_build.txt", "2024-05-01T12:00:00.0Z build log\n"),
+            ("3_build-docs.txt", "2024-05-01T12:00:00.0Z docs log\n"),
+            ("build-docs/1_Docs.txt", "docs step log\n"),
+        ]))
+        .unwrap();
+        let started = "2024-05-01T12:00:00Z";
+        let jobs = vec![
+            job(
+                "test / unit: linux",
+                &[(1, "Run tests", "success", started)],
+            ),
+            job("lint / rust", &[(1, "Clippy", "failure", started)]),
+            job("build", &[(1, "Build", "success", started)]),
+            job("build-docs", &[(1, "Docs", "success", started)]),
+        ];
+
+        assert_eq!(
+            step_lines(&job_logs(&files, jobs)),
+            [
+                [vec!["step log"]],
+                [vec!["2024-05-01T12:00:00.0Z lint log"]],
+                [vec!["2024-05-01T12:00:00.0Z build log"]],
+                [vec!["docs step log"]],
+            ]
+        );
+    }
+
+    #[test]
+    fn jobs_without_logs_have_empty_steps() {
+        let files = unzip(&archive(&[("0_other.txt", "log\n")])).unwrap();
+        let jobs = vec![job(
+            "build",
+            &[(1, "Build", "cancelled", "2024-05-01T12:00:00Z")],
+        )];
+        assert_eq!(step_lines(&job_logs(&files, jobs)), [[Vec::<&str>::new()]]);
+    }
+
+    #[test]
+    fn unzip_rejects_other_data() {
+        assert!(unzip(b"{\"message\": \"Not Found\"}").is_err());
+    }
+}
@@ -1,35 +1,48 @@
+#[macro_use]
+mod output;
+mod auth;
+mod config;
+mod detect;
+mod dry_run;
+mod git;
+mod github;
+mod journal;
+mod license;
+mod logs;
+mod project;
+mod prompt;
+mod repo;
+mod workflow;
+
+use auth::DeviceFlow;
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use colored::*;
-use dialoguer::{Input, Password, Select};
-use dirs::home_dir;
-use reqwest::blocking::Client;
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, USER_AGENT};
+use config::{load_config, read_config, Config, TokenSource};
+use detect::{detect_language, Language};
+use git::{
+    get_current_branch, get_origin, get_remote_url, has_commits, has_credential_helper,
+    is_git_repository, parse_remote_url, run_command, ssh_auth_works, tracked_files,
+};
+use github::{GitHubClient, GitHubError};
+use journal::{InitJournal, InitStep, RemoteChange};
+use output::{CliError, CommandResult, ErrorKind};
+use project::load_project_config;
+use repo::{CreatedRepo, RepoInfo, RepoSettings};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 use std::fs;
-use std::path::Path;
-use std::process::{Command, Output};
+use std::path::{Path, PathBuf};
 use std::str;
+use std::thread;
+use std::time::Duration;
 
-const CONFIG_DIR: &str = ".git_mate";
-const CONFIG_FILE: &str = "config.json";
-const GITIGNORE_API_URL: &str = "https://api.github.com/gitignore/templates";
 const VERSION: &str = "0.1.0";
+const TEMPLATE_FETCH_ATTEMPTS: u32 = 10;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Config {
-    github_token: String,
-    username: String,
-    default_branch: Option<String>,
-    default_license: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct RepoInfo {
-    name: String,
-    description: String,
-    private: bool,
-    license: Option<String>,
+#[derive(Debug)]
+struct CreatedItem {
+    number: u64,
+    html_url: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -44,6 +57,33 @@ fn main() {
         .author("Git Mate CLI")
         .about("Automates pushing local projects to GitHub")
         .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Print a machine-readable JSON result instead of colored text")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .help("Use the named account profile from the config file")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Show the git commands, file writes and GitHub requests without running them")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("no-input")
+                .long("no-input")
+                .visible_alias("yes")
+                .short("y")
+                .help("Never prompt; use defaults or fail on missing arguments")
+                .global(true),
+        )
         .subcommand(
             SubCommand::with_name("init")
                 .about("Initialize a new Git repository and prepare for GitHub")
@@ -61,6 +101,13 @@ fn main() {
                         .help("Repository description")
                         .takes_value(true),
                 )
+                .arg(
+                    Arg::with_name("org")
+                        .long("org")
+                        .short("o")
+                        .help("Create the repository under this organization")
+                        .takes_value(true),
+                )
                 .arg(
                     Arg::with_name("private")
                         .long("private")
@@ -68,6 +115,21 @@ fn main() {
                         .help("Make repository private")
                         .takes_value(false),
                 )
+                .arg(
+                    Arg::with_name("visibility")
+                        .long("visibility")
+                        .help("Repository visibility (internal is for organization repositories)")
+                        .takes_value(true)
+                        .possible_values(&["public", "private", "internal"])
+                        .conflicts_with("private"),
+                )
+                .args(&repo::settings_args())
+                .arg(
+                    Arg::with_name("template")
+                        .long("template")
+                        .help("Generate the repository from a template repository (owner/repo)")
+                        .takes_value(true),
+                )
                 .arg(
                     Arg::with_name("gitignore")
                         .long("gitignore")
@@ -82,12 +144,71 @@ fn main() {
                         .help("Add a license (e.g., MIT, Apache-2.0)")
                         .takes_value(true),
                 )
+                .arg(
+                    Arg::with_name("holder")
+                        .long("holder")
+                        .help("Copyright holder named in the license")
+                        .takes_value(true),
+                )
                 .arg(
                     Arg::with_name("workflow")
                         .long("workflow")
                         .short("w")
                         .help("Add a GitHub Actions workflow")
                         .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("protocol")
+                        .long("protocol")
+                        .help(
+                            "Protocol for the origin remote (default: SSH if it works, else HTTPS)",
+                        )
+                        .takes_value(true)
+                        .possible_values(&["ssh", "https"]),
+                )
+                .arg(
+                    Arg::with_name("resume")
+                        .long("resume")
+                        .help("Continue an init that failed partway")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("undo")
+                        .long("undo")
+                        .help("Revert what an unfinished init did")
+                        .takes_value(false)
+                        .conflicts_with("resume"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("repo")
+                .about("Manage the GitHub repository")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("edit")
+                        .about("Change settings of an existing repository")
+                        .arg(
+                            Arg::with_name("repo")
+                                .long("repo")
+                                .short("R")
+                                .help("Repository as owner/name (defaults to origin)")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("description")
+                                .long("desc")
+                                .short("d")
+                                .help("Repository description")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("visibility")
+                                .long("visibility")
+                                .help("Repository visibility")
+                                .takes_value(true)
+                                .possible_values(&["public", "private", "internal"]),
+                        )
+                        .args(&repo::settings_args()),
                 ),
         )
         .subcommand(
@@ -123,6 +244,88 @@ fn main() {
                         .long("default-branch")
                         .help("Default branch name")
                         .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("api-url")
+                        .long("api-url")
+                        .help("GitHub API base URL (e.g., https://ghe.example.com/api/v3)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .help("User or organization whose repositories use this profile")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("default-owner")
+                        .long("default-owner")
+                        .help("User or organization 'init' creates repositories under")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("default-license")
+                        .long("default-license")
+                        .help("License 'init' adds when --license is not given (e.g., mit)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("copyright-holder")
+                        .long("copyright-holder")
+                        .help("Name filled into LICENSE files (default: your GitHub profile name)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("git-protocol")
+                        .long("git-protocol")
+                        .help("Protocol for new remotes; auto picks SSH if it works")
+                        .takes_value(true)
+                        .possible_values(&["ssh", "https", "auto"]),
+                )
+                .arg(
+                    Arg::with_name("token-command")
+                        .long("token-command")
+                        .help("Command that prints the GitHub token (e.g., \"pass show github\")")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("no-verify")
+                        .long("no-verify")
+                        .help("Save without checking the token against GitHub")
+                        .takes_value(false),
+                )
+                .subcommand(
+                    SubCommand::with_name("show")
+                        .about("Show the effective configuration with the token masked"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("auth")
+                .about("Log in to GitHub in the browser")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("login")
+                        .about("Log in with the OAuth device flow and store the token")
+                        .arg(
+                            Arg::with_name("client-id")
+                                .long("client-id")
+                                .help("Client ID of the OAuth App to log in with")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("scopes")
+                                .long("scopes")
+                                .help("Space-separated OAuth scopes to request")
+                                .default_value(auth::LOGIN_SCOPES)
+                                .takes_value(true),
+                        ),
+                )
+                .subcommand(SubCommand::with_name("logout").about("Remove the stored token"))
+                .subcommand(
+                    SubCommand::with_name("status")
+                        .about("Show which account the token belongs to"),
                 ),
         )
         .subcommand(
@@ -166,6 +369,43 @@ fn main() {
                         .index(1),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("license")
+                .about("Add a LICENSE file")
+                .arg(
+                    Arg::with_name("license")
+                        .help("License key (e.g., mit, apache-2.0); defaults to the configured default license")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("holder")
+                        .long("holder")
+                        .help("Copyright holder named in the license (default: your GitHub profile name)")
+                        .takes_value(true),
+                )
+                .subcommand(
+                    SubCommand::with_name("headers")
+                        .about("Add SPDX license headers to tracked source files")
+                        .arg(
+                            Arg::with_name("license")
+                                .long("license")
+                                .help("SPDX identifier (e.g. GPL-3.0-or-later) or GitHub license key (e.g. mit); default: the configured default license")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("holder")
+                                .long("holder")
+                                .help("Copyright holder (default: your GitHub profile name)")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("check")
+                                .long("check")
+                                .help("Only list files without a header and fail if there are any")
+                                .takes_value(false),
+                        ),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("issue")
                 .about("Create a GitHub issue")
@@ -227,162 +467,600 @@ fn main() {
                 .about("Set up GitHub Actions workflow")
                 .arg(
                     Arg::with_name("type")
-                        .help("Workflow type (ci, deploy, custom)")
-                        .index(1)
-                        .possible_values(&["ci", "deploy", "custom"]),
+                        .help("Workflow type (ci, deploy, custom) or template name")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .short("f")
+                        .help("Overwrite the workflow file if it exists")
+                        .takes_value(false),
+                )
+                .subcommand(
+                    SubCommand::with_name("lint")
+                        .about("Check the workflows in .github/workflows for errors"),
+                )
+                .subcommand(
+                    SubCommand::with_name("runs")
+                        .about("List recent workflow runs for the current branch")
+                        .arg(
+                            Arg::with_name("branch")
+                                .long("branch")
+                                .short("b")
+                                .help("Branch to list runs for (default: the current branch)")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("all-branches")
+                                .long("all-branches")
+                                .help("List runs for every branch")
+                                .takes_value(false)
+                                .conflicts_with("branch"),
+                        )
+                        .arg(
+                            Arg::with_name("workflow")
+                                .long("workflow")
+                                .short("w")
+                                .help("Only runs of this workflow (file name, e.g. ci.yml)")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("limit")
+                                .long("limit")
+                                .short("L")
+                                .help("Number of runs to list")
+                                .default_value("10")
+                                .takes_value(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("run")
+                        .about("Trigger a workflow that has a workflow_dispatch event")
+                        .arg(
+                            Arg::with_name("workflow")
+                                .help("Workflow file name (e.g. deploy.yml) or ID")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::with_name("ref")
+                                .long("ref")
+                                .help("Branch or tag to run on (default: the current branch)")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("input")
+                                .long("input")
+                                .short("i")
+                                .help("Workflow input as key=value (repeatable)")
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("watch")
+                        .about("Wait for a workflow run to finish")
+                        .arg(
+                            Arg::with_name("run-id")
+                                .help("Run to watch (default: the latest run on the current branch)")
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::with_name("interval")
+                                .long("interval")
+                                .help("Seconds between status checks")
+                                .default_value("5")
+                                .takes_value(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("logs")
+                        .about("Show the logs of a workflow run, step by step")
+                        .arg(
+                            Arg::with_name("run-id")
+                                .help("Run to show the logs of")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::with_name("failed")
+                                .long("failed")
+                                .help("Only show the steps that failed")
+                                .takes_value(false),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("rerun")
+                        .about("Re-run a workflow run")
+                        .arg(
+                            Arg::with_name("run-id")
+                                .help("Run to re-run")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::with_name("failed")
+                                .long("failed")
+                                .help("Only re-run the failed jobs")
+                                .takes_value(false),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("cancel")
+                        .about("Cancel a workflow run")
+                        .arg(
+                            Arg::with_name("run-id")
+                                .help("Run to cancel")
+                                .required(true)
+                                .index(1),
+                        ),
                 ),
         );
     let matches = app.get_matches();
-    match matches.subcommand() {
-        ("init", Some(init_matches)) => init_command(init_matches),
-        ("push", Some(push_matches)) => push_command(push_matches),
-        ("config", Some(config_matches)) => config_command(config_matches),
-        ("branch", Some(branch_matches)) => branch_command(branch_matches),
-        ("gitignore", Some(gitignore_matches)) => gitignore_command(gitignore_matches),
-        ("issue", Some(issue_matches)) => issue_command(issue_matches),
-        ("pr", Some(pr_matches)) => pr_command(pr_matches),
-        ("workflow", Some(workflow_matches)) => workflow_command(workflow_matches),
+    output::set_json(matches.is_present("json"));
+    prompt::set_no_input(matches.is_present("no-input"));
+    dry_run::set_dry_run(matches.is_present("dry-run"));
+    config::set_profile(matches.value_of("profile"));
+
+    let (command, sub_matches) = matches.subcommand();
+    let sub_matches = match sub_matches {
+        Some(sub_matches) => sub_matches,
+        None => unreachable!(),
+    };
+    let result = match command {
+        "init" => init_command(sub_matches),
+        "repo" => repo_command(sub_matches),
+        "push" => push_command(sub_matches),
+        "config" => config_command(sub_matches),
+        "auth" => auth_command(sub_matches),
+        "branch" => branch_command(sub_matches),
+        "gitignore" => gitignore_command(sub_matches),
+        "license" => license_command(sub_matches),
+        "issue" => issue_command(sub_matches),
+        "pr" => pr_command(sub_matches),
+        "workflow" => workflow_command(sub_matches),
         _ => unreachable!(),
-    }
+    };
+    std::process::exit(output::finish(command, result));
 }
 
-fn init_command(matches: &ArgMatches) {
+fn init_command(matches: &ArgMatches) -> CommandResult {
     // check if already a git repository
     if is_git_repository() {
-        println!("{}", "This directory is already a git repository.".yellow());
+        say!("{}", "This directory is already a git repository.".yellow());
     } else {
         // Initialize git repository
-        match run_command("git", &["init"]) {
-            Ok(_) => println!("{}", "Git repository initialized successfully.".green()),
-            Err(e) => {
-                println!("{} {}", "Failed to initialize git repository:".red(), e);
-                return;
-            }
-        }
+        run_command("git", &["init"])
+            .map_err(|e| CliError::git("Failed to initialize git repository:", e))?;
+        say!("{}", "Git repository initialized successfully.".green());
     }
 
-    // get repository information
-    let repo_info = get_repository_info(matches);
-
     // load configuration
-    let config = match load_config() {
-        Ok(config) => config,
-        Err(_) => {
-            println!(
-                "{}",
-                "No GitHub configuration found. Please run 'git_mate config' first.".red()
-            );
-            return;
+    let config = require_config()?;
+
+    // pick up an unfinished run, or start a new one
+    let resume = matches.is_present("resume");
+    let undo = matches.is_present("undo");
+    let mut journal = match InitJournal::load().map_err(CliError::general)? {
+        Some(journal) if resume || undo => journal,
+        Some(_) => {
+            return Err(CliError::usage(
+                "A previous 'git_mate init' did not finish. Run 'git_mate init --resume' to continue it or 'git_mate init --undo' to revert it.",
+            ))
+        }
+        None if resume || undo => {
+            return Err(CliError::usage("There is no unfinished 'git_mate init' here."))
+        }
+        None => {
+            // get repository information
+            let mut repo_info = get_repository_info(matches, &config)?;
+
+            // reuse an existing origin or repository instead of failing halfway
+            let protocol = matches.value_of("protocol");
+            let existing = check_existing_repo(&config, &mut repo_info, protocol)?;
+
+            let mut journal = InitJournal::new(repo_info, existing);
+            journal.gitignore = matches.value_of("gitignore").map(|s| s.to_string());
+            journal.license = matches.value_of("license").map(|s| s.to_string());
+            journal.holder = matches.value_of("holder").map(|s| s.to_string());
+            journal.workflow = matches.value_of("workflow").map(|s| s.to_string());
+            journal.protocol = protocol.map(|s| s.to_string());
+            propose_project_files(&mut journal, &config)?;
+            journal.save().map_err(CliError::general)?;
+            journal
         }
     };
 
-    // set up .gitignore if requested
-    if let Some(template) = matches.value_of("gitignore") {
-        setup_gitignore(template, &config);
+    if undo {
+        undo_init(&config, &journal);
+        return Ok(json!({ "undone": true }));
+    }
+    if resume {
+        say!(
+            "{} {}",
+            "Resuming init of".cyan(),
+            journal.repo_info.name.bold()
+        );
     }
 
-    // set up license if requested
-    if let Some(license) = matches.value_of("license") {
-        setup_license(license, &config);
+    match run_init(&config, &mut journal) {
+        Ok(data) => {
+            InitJournal::remove();
+            Ok(data)
+        }
+        Err(e) if prompt::is_interactive() => {
+            say!("{} {}", "init failed:".red(), e);
+            let options = [
+                "Keep the changes and resume later with 'git_mate init --resume'",
+                "Undo the changes made by init",
+            ];
+            if prompt::select("What should init do?", &options, 0)? == 1 {
+                undo_init(&config, &journal);
+                Err(CliError::new(e.kind, "init failed and was undone."))
+            } else {
+                Err(CliError::new(
+                    e.kind,
+                    "init stopped. Run 'git_mate init --resume' to continue it.",
+                ))
+            }
+        }
+        Err(e) => Err(CliError::new(
+            e.kind,
+            format!(
+                "{}\nRun 'git_mate init --resume' to continue or 'git_mate init --undo' to revert.",
+                e.message
+            ),
+        )),
+    }
+}
+
+// the steps of init after the questions are answered. Each step is recorded
+// in the journal once done and skipped when resuming.
+fn run_init(config: &Config, journal: &mut InitJournal) -> CommandResult {
+    let save = |result: Result<(), String>| result.map_err(CliError::general);
+
+    // with a template or an existing repository these are written after its
+    // files are merged in, so they don't block the merge
+    let remote_history = journal.repo_info.template.is_some() || journal.linked;
+    if !remote_history && !journal.is_done(InitStep::ProjectFiles) {
+        setup_project_files(journal, config)?;
+        save(journal.complete(InitStep::ProjectFiles))?;
     }
 
     // create GitHub repository
-    match create_github_repo(&config, &repo_info) {
-        Ok(repo_url) => {
-            println!("{} {}", "GitHub repository created:".green(), repo_url);
-
-            // add remote
-            match run_command("git", &["remote", "add", "origin", &repo_url]) {
-                Ok(_) => println!("{}", "Remote 'origin' added successfully.".green()),
-                Err(e) => println!("{} {}", "Failed to add remote:".red(), e),
+    if !journal.is_done(InitStep::CreateRepo) {
+        let repo_info = &journal.repo_info;
+        let protocol = journal.protocol.as_deref();
+        let repo = match (journal.repo.take(), &repo_info.template) {
+            (Some(repo), _) => {
+                say!(
+                    "{} {}",
+                    "Linking existing repository:".green(),
+                    repo.html_url
+                );
+                repo
             }
+            (None, Some(template)) => {
+                let mut repo = generate_github_repo(config, template, repo_info)
+                    .map_err(|e| CliError::github("Failed to generate GitHub repository:", e))?;
+                choose_remote_url(config, protocol, &mut repo);
+                repo
+            }
+            (None, None) => {
+                let mut repo = create_github_repo(config, repo_info)
+                    .map_err(|e| CliError::github("Failed to create GitHub repository:", e))?;
+                choose_remote_url(config, protocol, &mut repo);
+                repo
+            }
+        };
+        if !journal.linked {
+            say!("{} {}", "GitHub repository created:".green(), repo.html_url);
+        }
+        journal.repo = Some(repo);
+        save(journal.complete(InitStep::CreateRepo))?;
+    }
+    let repo = journal
+        .repo
+        .clone()
+        .ok_or_else(|| CliError::general("The init journal has no repository."))?;
+
+    if !journal.is_done(InitStep::RepoSettings) {
+        let repo_info = &journal.repo_info;
+        // the generate endpoint takes none of the repository settings, and
+        // an existing repository keeps its visibility
+        if repo_info.template.is_some() || journal.linked {
+            let mut fields = repo_info.settings.fields();
+            if repo_info.visibility == "internal" && !journal.linked {
+                fields.insert("visibility".to_string(), json!("internal"));
+            }
+            if let Err(e) = update_repo(config, &repo.full_name, &fields) {
+                say!("{} {}", "Failed to update repository settings:".yellow(), e);
+            }
+        }
 
-            // create README.md if it doesn't exist
-            if !Path::new("README.md").exists() {
-                match fs::write(
-                    "README.md",
-                    format!("# {}\n\n{}", repo_info.name, repo_info.description),
-                ) {
-                    Ok(_) => println!("{}", "Created README.md file.".green()),
-                    Err(e) => println!("{} {}", "Failed to create README.md:".red(), e),
-                }
+        // topics can't be given on creation; the repository exists by now,
+        // so a failure here is not worth aborting for
+        if let Err(e) = set_topics(config, &repo.full_name, &repo_info.settings) {
+            say!("{} {}", "Failed to set repository topics:".yellow(), e);
+        }
+        save(journal.complete(InitStep::RepoSettings))?;
+    }
+
+    // add remote, or point an existing origin at the repository
+    if !journal.is_done(InitStep::Remote) {
+        let remote_url = repo.remote_url.clone();
+        match get_remote_url("origin") {
+            Ok(url) if url == remote_url => {}
+            Ok(url) => {
+                run_command("git", &["remote", "set-url", "origin", &remote_url])
+                    .map_err(|e| CliError::git("Failed to update remote:", e))?;
+                journal.remote = Some(RemoteChange::Replaced { previous_url: url });
+                say!("{}", "Remote 'origin' updated successfully.".green());
             }
+            Err(_) => {
+                run_command("git", &["remote", "add", "origin", &remote_url])
+                    .map_err(|e| CliError::git("Failed to add remote:", e))?;
+                journal.remote = Some(RemoteChange::Added);
+                say!("{}", "Remote 'origin' added successfully.".green());
+            }
+        }
+        save(journal.complete(InitStep::Remote))?;
+    }
+
+    if !journal.is_done(InitStep::MergeHistory) {
+        journal.merged = match &journal.repo_info.template {
+            Some(template) => merge_remote_history(&repo, &format!("template {}", template), true)?,
+            None if journal.linked => merge_remote_history(&repo, &repo.full_name, false)?,
+            None => false,
+        };
+        save(journal.complete(InitStep::MergeHistory))?;
+    }
+    if !journal.is_done(InitStep::ProjectFiles) {
+        setup_project_files(journal, config)?;
+        save(journal.complete(InitStep::ProjectFiles))?;
+    }
 
-            // set up GitHub Actions workflow if requested
-            if let Some(workflow_type) = matches.value_of("workflow") {
-                setup_workflow(workflow_type);
+    // create README.md if it doesn't exist
+    if !journal.is_done(InitStep::Readme) {
+        if !Path::new("README.md").exists() {
+            journal.record_file("README.md");
+            match dry_run::write(
+                "README.md",
+                format!(
+                    "# {}\n\n{}",
+                    journal.repo_info.name, journal.repo_info.description
+                ),
+            ) {
+                Ok(_) => say!("{}", "Created README.md file.".green()),
+                Err(e) => say!("{} {}", "Failed to create README.md:".red(), e),
             }
+        }
+        save(journal.complete(InitStep::Readme))?;
+    }
 
-            // add all files
-            match run_command("git", &["add", "."]) {
-                Ok(_) => println!("{}", "Added files to staging area.".green()),
-                Err(e) => println!("{} {}", "Failed to add files:".red(), e),
+    // set up GitHub Actions workflow if requested
+    if !journal.is_done(InitStep::Workflow) {
+        if let Some(workflow_type) = journal.workflow.clone() {
+            let (path, content) = prepare_workflow(&workflow_type, config)?;
+            if path.exists() {
+                say!(
+                    "{} {}",
+                    "Keeping the existing workflow".yellow(),
+                    path.display()
+                );
+            } else {
+                journal.record_file(&path.display().to_string());
+                write_workflow(&path, &content)?;
             }
+        }
+        save(journal.complete(InitStep::Workflow))?;
+    }
 
-            // initial commit
-            match run_command("git", &["commit", "-m", "Initial commit"]) {
-                Ok(_) => println!("{}", "Created initial commit.".green()),
-                Err(e) => println!("{} {}", "Failed to create initial commit:".red(), e),
+    if !journal.is_done(InitStep::Commit) {
+        // add all files
+        run_command("git", &["add", "."]).map_err(|e| CliError::git("Failed to add files:", e))?;
+        say!("{}", "Added files to staging area.".green());
+
+        // initial commit; on top of merged history only if something was added
+        let staged = run_command("git", &["diff", "--cached", "--quiet"]).is_err();
+        if journal.merged {
+            if staged {
+                run_command("git", &["commit", "-m", "Add project files"])
+                    .map_err(|e| CliError::git("Failed to commit project files:", e))?;
+                say!("{}", "Committed project files.".green());
             }
+        } else if staged || !has_commits() {
+            run_command("git", &["commit", "-m", "Initial commit"])
+                .map_err(|e| CliError::git("Failed to create initial commit:", e))?;
+            say!("{}", "Created initial commit.".green());
+        }
+        save(journal.complete(InitStep::Commit))?;
+    }
 
-            // get default branch from config or use main/master
-            let default_branch = config.default_branch.unwrap_or_else(|| "main".to_string());
-
-            // rename current branch if needed
-            match get_current_branch() {
-                Ok(current_branch) => {
-                    if current_branch != default_branch {
-                        match run_command("git", &["branch", "-M", &default_branch]) {
-                            Ok(_) => println!("{} {}", "Renamed branch to".green(), default_branch),
-                            Err(e) => println!("{} {}", "Failed to rename branch:".red(), e),
-                        }
-                    }
-                }
-                Err(_) => println!("{}", "Could not determine current branch.".yellow()),
+    // get default branch from the merged history, then config, or use main
+    let default_branch = repo
+        .default_branch
+        .clone()
+        .filter(|_| journal.merged)
+        .or_else(|| config.default_branch.clone())
+        .unwrap_or_else(|| "main".to_string());
+
+    // rename current branch if needed
+    match get_current_branch() {
+        Ok(current_branch) => {
+            if current_branch != default_branch {
+                run_command("git", &["branch", "-M", &default_branch])
+                    .map_err(|e| CliError::git("Failed to rename branch:", e))?;
+                say!("{} {}", "Renamed branch to".green(), default_branch);
             }
+        }
+        Err(_) => say!("{}", "Could not determine current branch.".yellow()),
+    }
+
+    // push to GitHub
+    run_command("git", &["push", "-u", "origin", &default_branch])
+        .map_err(|e| CliError::git("Failed to push to GitHub:", push_guidance(&repo, e)))?;
+    say!(
+        "{}",
+        "Project pushed to GitHub successfully!".green().bold()
+    );
+
+    let repo_info = &journal.repo_info;
+    Ok(json!({
+        "name": repo_info.name,
+        "owner": repo_info.owner,
+        "private": repo_info.visibility != "public",
+        "visibility": repo_info.visibility,
+        "html_url": repo.html_url,
+        "remote_url": repo.remote_url,
+        "protocol": if repo.ssh_url.as_ref() == Some(&repo.remote_url) { "ssh" } else { "https" },
+        "branch": default_branch,
+    }))
+}
 
-            // push to GitHub
-            match run_command("git", &["push", "-u", "origin", &default_branch]) {
-                Ok(_) => println!(
-                    "{}",
-                    "Project pushed to GitHub successfully!".green().bold()
+// revert what the journal says init did, as far as possible. Local commits
+// are kept since they may contain the user's own files.
+fn undo_init(config: &Config, journal: &InitJournal) {
+    if let (Some(repo), false) = (&journal.repo, journal.linked) {
+        if journal.is_done(InitStep::CreateRepo) {
+            let deleted = GitHubClient::new(config)
+                .and_then(|client| client.delete(&format!("/repos/{}", repo.full_name)));
+            match deleted {
+                Ok(()) => say!("{} {}", "Deleted GitHub repository".green(), repo.full_name),
+                Err(e) => say!(
+                    "{} {}: {}. Delete it from its settings page: {}/settings (needs the 'delete_repo' scope to do it here).",
+                    "Could not delete".yellow(),
+                    repo.full_name,
+                    e,
+                    repo.html_url
                 ),
-                Err(e) => println!("{} {}", "Failed to push to GitHub:".red(), e),
             }
         }
-        Err(e) => println!("{} {}", "Failed to create GitHub repository:".red(), e),
     }
+
+    let remote = match &journal.remote {
+        Some(RemoteChange::Added) => Some((
+            run_command("git", &["remote", "remove", "origin"]),
+            "Removed the 'origin' remote.",
+        )),
+        Some(RemoteChange::Replaced { previous_url }) => Some((
+            run_command("git", &["remote", "set-url", "origin", previous_url]),
+            "Restored the previous 'origin' URL.",
+        )),
+        None => None,
+    };
+    match remote {
+        Some((Ok(_), message)) => say!("{}", message.green()),
+        Some((Err(e), _)) => say!(
+            "{} {}",
+            "Failed to restore the 'origin' remote:".yellow(),
+            e
+        ),
+        None => {}
+    }
+
+    for file in journal.files.iter().rev() {
+        let result = match &file.previous {
+            Some(contents) => dry_run::write(&file.path, contents),
+            None => dry_run::remove_file(&file.path),
+        };
+        match result {
+            Ok(()) if file.previous.is_some() => say!("{} {}", "Restored".green(), file.path),
+            Ok(()) => {
+                say!("{} {}", "Removed".green(), file.path);
+                // and directories such as .github/workflows that init created
+                let mut dir = Path::new(&file.path).parent();
+                while let Some(parent) = dir.filter(|d| !d.as_os_str().is_empty()) {
+                    if dry_run::is_dry_run() {
+                        break;
+                    }
+                    if fs::remove_dir(parent).is_err() {
+                        break;
+                    }
+                    dir = parent.parent();
+                }
+            }
+            Err(e) => say!("{} {}: {}", "Failed to restore".yellow(), file.path, e),
+        }
+    }
+    if journal.is_done(InitStep::Commit) {
+        say!("{}", "Local commits were kept.".cyan());
+    }
+
+    InitJournal::remove();
 }
 
-fn push_command(matches: &ArgMatches) {
-    if !is_git_repository() {
-        println!(
-            "{}",
-            "Not a git repository. Run 'git_mate init' first.".red()
-        );
-        return;
+fn repo_command(matches: &ArgMatches) -> CommandResult {
+    match matches.subcommand() {
+        ("edit", Some(edit_matches)) => repo_edit_command(edit_matches),
+        _ => Err(CliError::usage("Unknown repo subcommand")),
+    }
+}
+
+fn repo_edit_command(matches: &ArgMatches) -> CommandResult {
+    let repo_name = match matches.value_of("repo") {
+        Some(name) => name.trim_matches('/').to_string(),
+        None => {
+            require_git_repository()?;
+            get_origin()
+                .map_err(|e| CliError::git("Failed to determine repository name:", e))?
+                .full_name()
+        }
+    };
+    let config = require_config()?;
+
+    let settings = RepoSettings::from_matches(matches)?;
+    let mut fields = settings.fields();
+    if let Some(description) = matches.value_of("description") {
+        fields.insert("description".to_string(), json!(description));
+    }
+    if let Some(visibility) = matches.value_of("visibility") {
+        fields.insert("visibility".to_string(), json!(visibility));
     }
+    if fields.is_empty() && settings.topics.is_none() {
+        return Err(CliError::usage(
+            "Nothing to change. Pass at least one setting, e.g. --topics or --merge-methods.",
+        ));
+    }
+
+    if !fields.is_empty() {
+        update_repo(&config, &repo_name, &fields)
+            .map_err(|e| CliError::github("Failed to update repository:", e))?;
+        say!("{} {}", "Updated settings of".green(), repo_name);
+    }
+    set_topics(&config, &repo_name, &settings)
+        .map_err(|e| CliError::github("Failed to set repository topics:", e))?;
+    if let Some(topics) = &settings.topics {
+        say!("{} {}", "Topics set to".green(), topics.join(", "));
+    }
+
+    Ok(json!({
+        "repository": repo_name,
+        "settings": fields,
+        "topics": settings.topics,
+    }))
+}
+
+fn push_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+
+    let project = load_project_config().map_err(CliError::config)?;
 
     // get commit message
     let message = match matches.value_of("message") {
         Some(msg) => msg.to_string(),
-        None => {
-            let default_msg = "Update";
-            match Input::<String>::new()
-                .with_prompt("Commit message")
-                .default(default_msg.to_string())
-                .interact()
-            {
-                Ok(msg) => msg,
-                Err(_) => {
-                    println!(
-                        "{}",
-                        "Failed to get commit message. Using default.".yellow()
-                    );
-                    default_msg.to_string()
-                }
-            }
-        }
+        None => prompt::input(
+            "Commit message",
+            Some(
+                project
+                    .commit
+                    .default_message
+                    .clone()
+                    .unwrap_or_else(|| "Update".to_string()),
+            ),
+        )?,
     };
 
     // check for uncommitted changes
@@ -391,75 +1069,73 @@ fn push_command(matches: &ArgMatches) {
             let changes = str::from_utf8(&output.stdout).unwrap_or("").trim();
 
             if changes.is_empty() {
-                println!("{}", "No changes to commit. Working tree clean.".yellow());
+                say!("{}", "No changes to commit. Working tree clean.".yellow());
 
                 // push anyway in case there are unpushed commits
-                let current_branch = match get_current_branch() {
-                    Ok(branch) => branch,
-                    Err(e) => {
-                        println!("{} {}", "Failed to get current branch:".red(), e);
-                        return;
-                    }
-                };
+                let current_branch = get_current_branch()
+                    .map_err(|e| CliError::git("Failed to get current branch:", e))?;
 
-                match run_command("git", &["push", "origin", &current_branch]) {
-                    Ok(_) => println!("{}", "Pushed existing commits to GitHub.".green()),
-                    Err(e) => println!("{} {}", "Failed to push:".red(), e),
-                }
+                run_command("git", &["push", "origin", &current_branch])
+                    .map_err(|e| CliError::git("Failed to push:", e))?;
+                say!("{}", "Pushed existing commits to GitHub.".green());
 
-                return;
+                return Ok(json!({
+                    "branch": current_branch,
+                    "committed": false,
+                }));
             }
         }
-        Err(e) => println!("{} {}", "Failed to check git status:".yellow(), e),
+        Err(e) => say!("{} {}", "Failed to check git status:".yellow(), e),
     }
 
+    // enforce the project's commit message convention
+    project
+        .commit
+        .check_message(&message)
+        .map_err(CliError::usage)?;
+
     // add all files
-    match run_command("git", &["add", "."]) {
-        Ok(_) => println!("{}", "Added files to staging area.".green()),
-        Err(e) => {
-            println!("{} {}", "Failed to add files:".red(), e);
-            return;
-        }
-    }
+    run_command("git", &["add", "."]).map_err(|e| CliError::git("Failed to add files:", e))?;
+    say!("{}", "Added files to staging area.".green());
 
     // commit changes
-    match run_command("git", &["commit", "-m", &message]) {
-        Ok(_) => println!("{}", "Changes committed successfully.".green()),
-        Err(e) => {
-            println!("{} {}", "Failed to commit changes:".red(), e);
-            return;
-        }
-    }
+    run_command("git", &["commit", "-m", &message])
+        .map_err(|e| CliError::git("Failed to commit changes:", e))?;
+    say!("{}", "Changes committed successfully.".green());
 
     // push to github
-    let current_branch = match get_current_branch() {
-        Ok(branch) => branch,
-        Err(e) => {
-            println!("{} {}", "Failed to get current branch:".red(), e);
-            return;
-        }
-    };
+    let current_branch =
+        get_current_branch().map_err(|e| CliError::git("Failed to get current branch:", e))?;
+
+    run_command("git", &["push", "origin", &current_branch])
+        .map_err(|e| CliError::git("Failed to push changes:", e))?;
+    say!(
+        "{}",
+        "Changes pushed to GitHub successfully!".green().bold()
+    );
 
-    match run_command("git", &["push", "origin", &current_branch]) {
-        Ok(_) => println!(
-            "{}",
-            "Changes pushed to GitHub successfully!".green().bold()
-        ),
-        Err(e) => println!("{} {}", "Failed to push changes:".red(), e),
-    }
+    Ok(json!({
+        "branch": current_branch,
+        "committed": true,
+        "message": message,
+    }))
 }
 
-fn config_command(matches: &ArgMatches) {
-    let mut config = load_config().unwrap_or_else(|_| Config {
-        github_token: String::new(),
-        username: String::new(),
-        default_branch: Some("main".to_string()),
-        default_license: None,
-    });
+fn config_command(matches: &ArgMatches) -> CommandResult {
+    if let ("show", Some(_)) = matches.subcommand() {
+        return config_show_command();
+    }
+
+    // edit a named profile with --profile, otherwise the top-level default
+    let (edit, mut config) = config::edit_profile()
+        .map_err(|e| CliError::config(format!("Failed to read configuration: {}", e)))?;
+    let profile = edit.name.clone();
 
-    // update token if provided
+    // update token if provided (empty removes it from the file)
+    let mut new_token = false;
     if let Some(token) = matches.value_of("token") {
         config.github_token = token.to_string();
+        new_token = !token.is_empty();
     }
 
     // update username if provided
@@ -472,324 +1148,1312 @@ fn config_command(matches: &ArgMatches) {
         config.default_branch = Some(branch.to_string());
     }
 
+    // update API base URL if provided (empty resets to github.com)
+    if let Some(api_url) = matches.value_of("api-url") {
+        let api_url = api_url.trim().trim_end_matches('/');
+        config.api_url = if api_url.is_empty() {
+            None
+        } else {
+            Some(api_url.to_string())
+        };
+    }
+
+    // update the owners this profile is picked for if provided
+    if let Some(owners) = matches.values_of("owner") {
+        config.owners = owners
+            .map(|owner| owner.trim().to_string())
+            .filter(|owner| !owner.is_empty())
+            .collect();
+    }
+
+    // update default owner if provided (empty means the user's own account)
+    if let Some(owner) = matches.value_of("default-owner") {
+        let owner = owner.trim();
+        config.default_owner = if owner.is_empty() {
+            None
+        } else {
+            Some(owner.to_string())
+        };
+    }
+
+    // update default license if provided (empty removes it)
+    if let Some(license) = matches.value_of("default-license") {
+        let license = license.trim();
+        config.default_license = if license.is_empty() {
+            None
+        } else {
+            Some(license.to_lowercase())
+        };
+    }
+
+    // update copyright holder if provided (empty removes it)
+    if let Some(holder) = matches.value_of("copyright-holder") {
+        let holder = holder.trim();
+        config.copyright_holder = if holder.is_empty() {
+            None
+        } else {
+            Some(holder.to_string())
+        };
+    }
+
+    // update git protocol if provided ("auto" removes it)
+    if let Some(protocol) = matches.value_of("git-protocol") {
+        config.git_protocol = Some(protocol.to_string()).filter(|p| p != "auto");
+    }
+
+    // update token command if provided (empty removes it)
+    if let Some(command) = matches.value_of("token-command") {
+        let command = command.trim();
+        config.token_command = if command.is_empty() {
+            None
+        } else {
+            Some(command.to_string())
+        };
+    }
+
+    config
+        .resolve_token()
+        .map_err(|e| CliError::config(format!("Failed to get GitHub token: {}", e)))?;
+
     // if no arguments provided, prompt interactively
     if !matches.is_present("token")
         && !matches.is_present("username")
         && !matches.is_present("default-branch")
+        && !matches.is_present("api-url")
+        && !matches.is_present("token-command")
+        && !matches.is_present("owner")
+        && !matches.is_present("default-owner")
+        && !matches.is_present("default-license")
+        && !matches.is_present("copyright-holder")
+        && !matches.is_present("git-protocol")
+        && prompt::is_interactive()
     {
-        println!("{}", "GitHub Configuration".cyan().bold());
-        println!("{}", "Please provide your GitHub credentials.".cyan());
+        match &profile {
+            Some(name) => say!(
+                "{} {}",
+                "GitHub Configuration for profile".cyan().bold(),
+                name
+            ),
+            None => say!("{}", "GitHub Configuration".cyan().bold()),
+        }
+        say!("{}", "Please provide your GitHub credentials.".cyan());
+
+        // get API base URL for named profiles, which are often on another host
+        if profile.is_some() {
+            let api_url = prompt::input("GitHub API base URL", Some(config.api_url()))?;
+            let api_url = api_url.trim().trim_end_matches('/');
+            config.api_url = if api_url.is_empty() || api_url == config::DEFAULT_API_URL {
+                None
+            } else {
+                Some(api_url.to_string())
+            };
+            config
+                .resolve_token()
+                .map_err(|e| CliError::config(format!("Failed to get GitHub token: {}", e)))?;
+        }
 
         // get username
-        config.username = Input::new()
-            .with_prompt("GitHub username")
-            .default(config.username.clone())
-            .interact()
-            .unwrap_or_else(|_| config.username.clone());
-
-        // get token
-        config.github_token = Password::new()
-            .with_prompt("GitHub Personal Access Token (with repo scope)")
-            .with_confirmation("Confirm token", "Tokens don't match")
-            .interact()
-            .unwrap_or_else(|_| config.github_token);
+        config.username = prompt::input("GitHub username", Some(config.username.clone()))?;
+
+        // get token, unless it already comes from somewhere other than this file
+        match config.token_source() {
+            Some(source) if *source != TokenSource::ConfigFile => {
+                say!("{} {}", "Using GitHub token from".cyan(), source)
+            }
+            _ => {
+                config.github_token =
+                    prompt::password("GitHub Personal Access Token (with repo scope)", "--token")?;
+                new_token = true;
+                config
+                    .resolve_token()
+                    .map_err(|e| CliError::config(format!("Failed to get GitHub token: {}", e)))?;
+            }
+        }
 
         // get default branch
-        config.default_branch = Some(
-            Input::new()
-                .with_prompt("Default branch name")
-                .default(config.default_branch.unwrap_or_else(|| "main".to_string()))
-                .interact()
-                .unwrap_or_else(|_| "main".to_string()),
-        );
+        config.default_branch = Some(prompt::input(
+            "Default branch name",
+            Some(
+                config
+                    .default_branch
+                    .clone()
+                    .unwrap_or_else(|| "main".to_string()),
+            ),
+        )?);
+
+        // get default license; not worth failing the whole setup over
+        match select_license(
+            &config,
+            "Default license for new repositories",
+            config.default_license.as_deref(),
+            true,
+        ) {
+            Ok(license) => config.default_license = license,
+            Err(e) => say!("{} {}", "Skipping default license:".yellow(), e),
+        }
+    }
+
+    // validate config. The username can be filled in from the token.
+    let verify = !matches.is_present("no-verify");
+    if config.token().is_empty() || (config.username.is_empty() && !verify) {
+        if !prompt::is_interactive() {
+            let mut missing = Vec::new();
+            if config.username.is_empty() && !verify {
+                missing.push("--username");
+            }
+            if config.token().is_empty() {
+                missing.push("--token");
+            }
+            return Err(prompt::missing_args(&missing));
+        }
+        return Err(CliError::config("GitHub token and username are required."));
     }
 
-    // validate config.
-    if config.github_token.is_empty() || config.username.is_empty() {
-        println!("{}", "GitHub token and username are required.".red());
-        return;
+    // a token from the environment or token_command wins over the one
+    // being saved, but it's the new token that has to be checked
+    let overridden_by = config
+        .token_source()
+        .filter(|source| new_token && **source != TokenSource::ConfigFile)
+        .map(|source| source.to_string());
+    if let Some(source) = &overridden_by {
+        say!(
+            "{} the token from {} takes precedence over the one being saved.",
+            "Warning:".yellow(),
+            source
+        );
+        config.use_file_token();
     }
 
+    // check the token against GitHub before saving it
+    let identity = if verify {
+        verify_token(&mut config, matches.is_present("username"))?
+    } else {
+        Value::Null
+    };
+
+    let result = json!({
+        "profile": edit.profile_name(),
+        "username": config.username,
+        "default_branch": config.default_branch,
+        "default_license": config.default_license,
+        "api_url": config.api_url(),
+        "token_source": config.token_source().map(|source| source.to_string()),
+        "overridden_by": overridden_by,
+        "identity": identity,
+    });
+    let token_in_file = !config.github_token.is_empty();
+
     // save config.
-    match save_config(&config) {
-        Ok(_) => println!("{}", "Configuration saved successfully.".green()),
-        Err(e) => println!("{} {}", "Failed to save configuration:".red(), e),
+    edit.save(config)
+        .map_err(|e| CliError::config(format!("Failed to save configuration: {}", e)))?;
+    say!("{}", "Configuration saved successfully.".green());
+    if token_in_file {
+        say!(
+            "{}",
+            "The token is stored in ~/.git_mate/config.json (readable only by you). Set GITHUB_TOKEN or use --token-command to keep it out of the file.".yellow()
+        );
     }
+
+    Ok(result)
 }
 
-fn branch_command(matches: &ArgMatches) {
-    if !is_git_repository() {
-        println!(
+// call GET /user to check the token, fill in or correct the username and
+// report the token's scopes. A username given with --username is only
+// replaced when the user agrees at the prompt.
+fn verify_token(config: &mut Config, username_given: bool) -> Result<Value, CliError> {
+    let user = match GitHubClient::new(config).and_then(|client| client.current_user()) {
+        Ok(user) => user,
+        Err(e @ GitHubError::Auth { .. }) => {
+            return Err(CliError::github("GitHub rejected the token:", e))
+        }
+        Err(e) => {
+            say!("{} {}", "Could not verify the token:".yellow(), e);
+            return Ok(Value::Null);
+        }
+    };
+
+    match &user.name {
+        Some(name) => say!(
+            "{} {} ({})",
+            "Authenticated as".green(),
+            user.login.bold(),
+            name
+        ),
+        None => say!("{} {}", "Authenticated as".green(), user.login.bold()),
+    }
+
+    if config.username.is_empty() {
+        config.username = user.login.clone();
+        say!("{} {}", "Username set to".green(), user.login);
+    } else if !config.username.eq_ignore_ascii_case(&user.login) {
+        say!(
+            "{} '{}' does not match the token's account '{}'.",
+            "Warning:".yellow(),
+            config.username,
+            user.login
+        );
+        if prompt::confirm(
+            &format!("Use '{}' as the username?", user.login),
+            !username_given,
+        )? {
+            config.username = user.login.clone();
+        }
+    }
+
+    match &user.scopes {
+        Some(scopes) if scopes.is_empty() => say!("{} (none)", "Token scopes:".cyan()),
+        Some(scopes) => say!("{} {}", "Token scopes:".cyan(), scopes.join(", ")),
+        None => say!(
             "{}",
-            "Not a git repository. Run 'git_mate init' first.".red()
+            "Token scopes are not reported for this token type (e.g. fine-grained tokens).".cyan()
+        ),
+    }
+
+    // scope needed by each command
+    let required = [("repo", "init"), ("workflow", "workflow")];
+    let missing: Vec<&str> = required
+        .iter()
+        .filter(|(scope, _)| !user.has_scope(scope))
+        .map(|(scope, command)| {
+            say!(
+                "{} the token lacks the '{}' scope needed by 'git_mate {}'.",
+                "Warning:".yellow(),
+                scope,
+                command
+            );
+            *scope
+        })
+        .collect();
+
+    Ok(json!({
+        "login": user.login,
+        "name": user.name,
+        "scopes": user.scopes,
+        "missing_scopes": missing,
+    }))
+}
+
+fn config_show_command() -> CommandResult {
+    let config = config::load_effective_config().map_err(CliError::config)?;
+    let mut profiles = vec![config::DEFAULT_PROFILE.to_string()];
+    if let Some(file) = read_config().map_err(CliError::config)? {
+        profiles.extend(file.profiles.into_keys());
+    }
+
+    let not_set = || "(not set)".to_string();
+    let token = match config.token_source() {
+        Some(source) => format!("{} (from {})", config::mask_token(config.token()), source),
+        None => not_set(),
+    };
+    let rows = [
+        ("Profile", config.active_profile().to_string()),
+        (
+            "Username",
+            Some(config.username.clone())
+                .filter(|u| !u.is_empty())
+                .unwrap_or_else(not_set),
+        ),
+        ("API URL", config.api_url()),
+        ("Web URL", config.web_url()),
+        (
+            "Default branch",
+            config.default_branch.clone().unwrap_or_else(not_set),
+        ),
+        (
+            "Default license",
+            config.default_license.clone().unwrap_or_else(not_set),
+        ),
+        (
+            "License holder",
+            config
+                .copyright_holder
+                .clone()
+                .unwrap_or_else(|| "GitHub profile name".to_string()),
+        ),
+        ("Token", token),
+        (
+            "Default owner",
+            config
+                .default_owner
+                .clone()
+                .unwrap_or_else(|| config.username.clone()),
+        ),
+        (
+            "Git protocol",
+            config
+                .git_protocol
+                .clone()
+                .unwrap_or_else(|| "auto".to_string()),
+        ),
+        (
+            "Token command",
+            config.token_command.clone().unwrap_or_else(not_set),
+        ),
+        ("Profiles", profiles.join(", ")),
+    ];
+
+    say!("{}", "Effective configuration".cyan().bold());
+    for (label, value) in &rows {
+        say!(
+            "{} {}",
+            format!("{:<16}", format!("{}:", label)).bold(),
+            value
         );
-        return;
     }
 
+    Ok(json!({
+        "profile": config.active_profile(),
+        "username": config.username,
+        "api_url": config.api_url(),
+        "web_url": config.web_url(),
+        "default_branch": config.default_branch,
+        "default_license": config.default_license,
+        "copyright_holder": config.copyright_holder,
+        "default_owner": config.default_owner,
+        "git_protocol": config.git_protocol,
+        "token": config::mask_token(config.token()),
+        "token_source": config.token_source().map(|source| source.to_string()),
+        "token_command": config.token_command,
+        "owners": config.owners,
+        "profiles": profiles,
+    }))
+}
+
+fn auth_command(matches: &ArgMatches) -> CommandResult {
+    match matches.subcommand() {
+        ("login", Some(login_matches)) => auth_login_command(login_matches),
+        ("logout", Some(_)) => auth_logout_command(),
+        ("status", Some(_)) => auth_status_command(),
+        _ => Err(CliError::usage("Unknown auth subcommand")),
+    }
+}
+
+fn auth_login_command(matches: &ArgMatches) -> CommandResult {
+    let (edit, mut config) = config::edit_profile()
+        .map_err(|e| CliError::config(format!("Failed to read configuration: {}", e)))?;
+
+    if let Some(client_id) = matches.value_of("client-id") {
+        config.oauth_client_id = Some(client_id.trim().to_string());
+    }
+    let client_id = config.oauth_client_id().ok_or_else(|| {
+        CliError::config(
+            "No OAuth client ID configured. Register an OAuth App with device flow enabled, then pass --client-id or set GIT_MATE_OAUTH_CLIENT_ID.",
+        )
+    })?;
+
+    if dry_run::is_dry_run() {
+        return Err(CliError::usage(
+            "'auth login' waits for you to authorize in the browser and can't be previewed with --dry-run.",
+        ));
+    }
+
+    let flow = DeviceFlow::new(&config.web_url(), &client_id);
+    let code = flow
+        .request_code(matches.value_of("scopes").unwrap_or(auth::LOGIN_SCOPES))
+        .map_err(|e| CliError::github("Failed to start login:", e))?;
+
+    say!(
+        "{} {}",
+        "First copy your one-time code:".cyan(),
+        code.user_code.bold()
+    );
+    say!(
+        "{} {} {}",
+        "Then open".cyan(),
+        code.verification_uri,
+        "in your browser and enter the code.".cyan()
+    );
+
+    let spinner = output::spinner("Waiting for authorization...");
+    let granted = flow.poll_token(&code);
+    spinner.finish_and_clear();
+    let granted = granted.map_err(|e| CliError::github("Login failed:", e))?;
+
+    config.github_token = granted.token;
+    config
+        .resolve_token()
+        .map_err(|e| CliError::config(format!("Failed to get GitHub token: {}", e)))?;
+    if let Some(source) = config
+        .token_source()
+        .filter(|s| **s != TokenSource::ConfigFile)
+    {
+        say!(
+            "{} the token from {} takes precedence over the one just stored.",
+            "Warning:".yellow(),
+            source
+        );
+        config.use_file_token();
+    }
+    let identity = verify_token(&mut config, false)?;
+
+    let result = json!({
+        "profile": edit.profile_name(),
+        "username": config.username,
+        "scopes": granted.scopes,
+        "identity": identity,
+    });
+    edit.save(config)
+        .map_err(|e| CliError::config(format!("Failed to save configuration: {}", e)))?;
+    say!("{}", "Logged in and saved the token.".green().bold());
+
+    Ok(result)
+}
+
+fn auth_logout_command() -> CommandResult {
+    let (edit, mut config) = config::edit_profile()
+        .map_err(|e| CliError::config(format!("Failed to read configuration: {}", e)))?;
+    let web_url = config.web_url();
+    let had_token = !config.github_token.is_empty();
+
+    config.github_token.clear();
+    config
+        .resolve_token()
+        .map_err(|e| CliError::config(format!("Failed to get GitHub token: {}", e)))?;
+    let remaining = config.token_source().map(|source| source.to_string());
+
+    if had_token {
+        edit.save(config)
+            .map_err(|e| CliError::config(format!("Failed to save configuration: {}", e)))?;
+        say!("{}", "Removed the stored token.".green());
+        say!(
+            "{} {}/settings/applications",
+            "To revoke it on GitHub as well, visit".cyan(),
+            web_url
+        );
+    } else {
+        say!("{}", "No token is stored for this profile.".yellow());
+    }
+    if let Some(source) = &remaining {
+        say!(
+            "{} a token is still provided by {}.",
+            "Warning:".yellow(),
+            source
+        );
+    }
+
+    Ok(json!({
+        "removed": had_token,
+        "remaining_token_source": remaining,
+    }))
+}
+
+fn auth_status_command() -> CommandResult {
+    let config = config::load_effective_config().map_err(CliError::config)?;
+    let web_url = config.web_url();
+
+    let source = config.token_source().ok_or_else(|| {
+        CliError::new(
+            ErrorKind::Auth,
+            format!("Not logged in to {}. Run 'git_mate auth login'.", web_url),
+        )
+    })?;
+    let user = GitHubClient::new(&config)
+        .and_then(|client| client.current_user())
+        .map_err(|e| CliError::github("The token is not valid:", e))?;
+
+    say!(
+        "{} {} {} {}",
+        "Logged in to".green(),
+        web_url,
+        "as".green(),
+        user.login.bold()
+    );
+    say!("{} {}", "Profile:".cyan(), config.active_profile());
+    say!(
+        "{} {} (from {})",
+        "Token:".cyan(),
+        config::mask_token(config.token()),
+        source
+    );
+    match &user.scopes {
+        Some(scopes) => say!("{} {}", "Scopes:".cyan(), scopes.join(", ")),
+        None => say!("{} (not reported for this token type)", "Scopes:".cyan()),
+    }
+
+    Ok(json!({
+        "host": web_url,
+        "profile": config.active_profile(),
+        "login": user.login,
+        "token_source": source.to_string(),
+        "scopes": user.scopes,
+    }))
+}
+
+fn branch_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+
     match matches.subcommand() {
         ("create", Some(create_matches)) => {
             let branch_name = create_matches.value_of("name").unwrap();
 
             // create the branch
-            match run_command("git", &["branch", branch_name]) {
-                Ok(_) => {
-                    println!("{} {}", "Branch created:".green(), branch_name);
-
-                    // checkout if requested
-                    if create_matches.is_present("checkout") {
-                        match run_command("git", &["checkout", branch_name]) {
-                            Ok(_) => println!("{} {}", "Switched to branch:".green(), branch_name),
-                            Err(e) => println!("{} {}", "Failed to switch branch:".red(), e),
-                        }
-                    }
-                }
-                Err(e) => println!("{} {}", "Failed to create branch:".red(), e),
+            run_command("git", &["branch", branch_name])
+                .map_err(|e| CliError::git("Failed to create branch:", e))?;
+            say!("{} {}", "Branch created:".green(), branch_name);
+
+            // checkout if requested
+            let checkout = create_matches.is_present("checkout");
+            if checkout {
+                run_command("git", &["checkout", branch_name])
+                    .map_err(|e| CliError::git("Failed to switch branch:", e))?;
+                say!("{} {}", "Switched to branch:".green(), branch_name);
             }
+
+            Ok(json!({ "branch": branch_name, "checked_out": checkout }))
+        }
+        ("list", _) => {
+            let output = run_command("git", &["branch"])
+                .map_err(|e| CliError::git("Failed to list branches:", e))?;
+            let branches = str::from_utf8(&output.stdout)
+                .unwrap_or("Could not parse branches")
+                .trim();
+            say!("{}", "Branches:".cyan());
+            say!("{}", branches);
+
+            let current = branches
+                .lines()
+                .find_map(|line| line.strip_prefix("* "))
+                .map(|name| name.trim().to_string());
+            let names: Vec<String> = branches
+                .lines()
+                .map(|line| line.trim_start_matches('*').trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect();
+            Ok(json!({ "branches": names, "current": current }))
         }
-        ("list", _) => match run_command("git", &["branch"]) {
-            Ok(output) => {
-                let branches = str::from_utf8(&output.stdout)
-                    .unwrap_or("Could not parse branches")
-                    .trim();
-                println!("{}", "Branches:".cyan());
-                println!("{}", branches);
-            }
-            Err(e) => println!("{} {}", "Failed to list branches:".red(), e),
-        },
         ("switch", Some(switch_matches)) => {
             let branch_name = switch_matches.value_of("name").unwrap();
 
-            match run_command("git", &["checkout", branch_name]) {
-                Ok(_) => println!("{} {}", "Switched to branch:".green(), branch_name),
-                Err(e) => println!("{} {}", "Failed to switch branch:".red(), e),
-            }
+            run_command("git", &["checkout", branch_name])
+                .map_err(|e| CliError::git("Failed to switch branch:", e))?;
+            say!("{} {}", "Switched to branch:".green(), branch_name);
+
+            Ok(json!({ "branch": branch_name }))
         }
-        _ => println!("{}", "Unknown branch subcommand".red()),
+        _ => Err(CliError::usage("Unknown branch subcommand")),
     }
 }
 
-fn gitignore_command(matches: &ArgMatches) {
-    let config = match load_config() {
-        Ok(config) => config,
-        Err(_) => {
-            println!(
-                "{}",
-                "No GitHub configuration found. Please run 'git_mate config' first.".red()
-            );
-            return;
-        }
-    };
+fn gitignore_command(matches: &ArgMatches) -> CommandResult {
+    prompt::require_args(matches, &[("template", "<template>")])?;
+    let config = require_config()?;
 
     let template = match matches.value_of("template") {
         Some(template) => template.to_string(),
         None => {
             // interactive template selection
-            let templates = get_gitignore_templates(&config);
-            match templates {
-                Ok(templates) => {
-                    let selection = Select::new()
-                        .with_prompt("Select a .gitignore template")
-                        .items(&templates)
-                        .interact();
-
-                    match selection {
-                        Ok(index) => templates[index].clone(),
-                        Err(_) => {
-                            println!("{}", "Template selection cancelled.".yellow());
-                            return;
-                        }
-                    }
-                }
-                Err(e) => {
-                    println!("{} {}", "Failed to fetch .gitignore templates:".red(), e);
-                    return;
-                }
-            }
+            let templates = get_gitignore_templates(&config)
+                .map_err(|e| CliError::github("Failed to fetch .gitignore templates:", e))?;
+            let selection = prompt::select("Select a .gitignore template", &templates, 0)?;
+            templates[selection].clone()
         }
     };
 
-    setup_gitignore(&template, &config);
+    setup_gitignore(&template, &config)?;
+
+    Ok(json!({ "template": template, "path": ".gitignore" }))
 }
 
-fn issue_command(matches: &ArgMatches) {
-    if !is_git_repository() {
-        println!(
-            "{}",
-            "Not a git repository. Run 'git_mate init' first.".red()
-        );
-        return;
+fn license_command(matches: &ArgMatches) -> CommandResult {
+    if let ("headers", Some(headers_matches)) = matches.subcommand() {
+        return license_headers_command(headers_matches);
     }
-
-    let config = match load_config() {
-        Ok(config) => config,
-        Err(_) => {
-            println!(
-                "{}",
-                "No GitHub configuration found. Please run 'git_mate config' first.".red()
-            );
-            return;
+    let config = require_config()?;
+
+    let license = match matches.value_of("license") {
+        Some(license) => license.to_lowercase(),
+        None if !prompt::is_interactive() => config
+            .default_license
+            .clone()
+            .ok_or_else(|| prompt::missing_args(&["<license>"]))?,
+        None => {
+            // interactive license selection
+            select_license(
+                &config,
+                "Select a license",
+                config.default_license.as_deref(),
+                false,
+            )?
+            .ok_or_else(|| CliError::usage("No license selected."))?
         }
     };
 
-    let repo_name = match get_repo_name_from_remote() {
-        Ok(name) => name,
-        Err(e) => {
-            println!("{} {}", "Failed to determine repository name:".red(), e);
-            return;
+    let holder = license::copyright_holder(&config, matches.value_of("holder"));
+    setup_license(&license, &holder, &config)?;
+
+    Ok(json!({ "license": license, "holder": holder, "path": "LICENSE" }))
+}
+
+fn license_headers_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+
+    // tracked files that take comments and have no header yet
+    let files = tracked_files().map_err(|e| CliError::git("Failed to list files:", e))?;
+    let mut missing = Vec::new();
+    for path in files {
+        let style = match license::comment_style(&path) {
+            Some(style) => style,
+            None => continue,
+        };
+        // binary files and symlinked or deleted paths are left alone
+        match fs::read(&path).map(String::from_utf8) {
+            Ok(Ok(contents)) if !license::has_header(&contents) => {
+                missing.push((path, style, contents))
+            }
+            _ => {}
         }
-    };
+    }
+
+    if matches.is_present("check") {
+        let paths: Vec<&str> = missing.iter().map(|(path, _, _)| path.as_str()).collect();
+        if paths.is_empty() {
+            say!("{}", "All source files have a license header.".green());
+            return Ok(json!({ "missing": paths }));
+        }
+        for path in &paths {
+            say!("{} {}", "Missing license header:".yellow(), path);
+        }
+        return Err(CliError::new(
+            ErrorKind::Validation,
+            format!("{} file(s) have no license header.", paths.len()),
+        ));
+    }
+
+    // a token is only needed to map a GitHub license key
+    let config = config::load_effective_config().map_err(CliError::config)?;
+    let license = matches
+        .value_of("license")
+        .map(|license| license.trim().to_string())
+        .or_else(|| config.default_license.clone())
+        .filter(|license| !license.is_empty())
+        .ok_or_else(|| {
+            CliError::usage("No license given. Pass --license or set one with 'git_mate config --default-license'.")
+        })?;
+    let spdx_id = spdx_id(&config, &license)?;
+    let holder = license::copyright_holder(&config, matches.value_of("holder"));
+    let year = license::current_year();
+
+    let mut updated = Vec::new();
+    for (path, style, contents) in missing {
+        let header = license::header(style, &spdx_id, year, &holder);
+        dry_run::write(&path, license::insert_header(&contents, &header))
+            .map_err(|e| CliError::general(format!("Failed to write {}: {}", path, e)))?;
+        say!("{} {}", "Added license header:".green(), path);
+        updated.push(path);
+    }
+    if updated.is_empty() {
+        say!(
+            "{}",
+            "All source files already have a license header.".green()
+        );
+    }
+
+    Ok(json!({
+        "license": spdx_id,
+        "holder": holder,
+        "updated": updated,
+    }))
+}
+
+// pick a license key from GitHub's collection, preselecting `current`.
+// With `allow_none` the first option is to have no license.
+fn select_license(
+    config: &Config,
+    prompt: &str,
+    current: Option<&str>,
+    allow_none: bool,
+) -> Result<Option<String>, CliError> {
+    let licenses = GitHubClient::new(config)
+        .and_then(|client| client.licenses())
+        .map_err(|e| CliError::github("Failed to fetch licenses:", e))?;
+
+    let mut options: Vec<String> = licenses
+        .iter()
+        .map(|license| format!("{} ({})", license.name, license.key))
+        .collect();
+    let mut keys: Vec<Option<String>> = licenses.into_iter().map(|l| Some(l.key)).collect();
+    if allow_none {
+        options.insert(0, "None".to_string());
+        keys.insert(0, None);
+    }
+
+    let default = current
+        .and_then(|current| {
+            keys.iter().position(|key| {
+                key.as_deref()
+                    .is_some_and(|key| key.eq_ignore_ascii_case(current))
+            })
+        })
+        .unwrap_or(0);
+    let selection = prompt::select(prompt, &options, default)?;
+    Ok(keys.swap_remove(selection))
+}
+
+fn issue_command(matches: &ArgMatches) -> CommandResult {
+    prompt::require_args(matches, &[("title", "--title")])?;
+    require_git_repository()?;
+    let config = require_config()?;
+
+    let repo_name = get_origin()
+        .map_err(|e| CliError::git("Failed to determine repository name:", e))?
+        .full_name();
 
     let issue = IssueInfo {
-        title: matches
-            .value_of("title")
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| {
-                Input::new()
-                    .with_prompt("Issue title")
-                    .interact()
-                    .unwrap_or_else(|_| "New Issue".to_string())
-            }),
-        body: matches
-            .value_of("body")
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| {
-                Input::new()
-                    .with_prompt("Issue description")
-                    .interact()
-                    .unwrap_or_else(|_| "".to_string())
-            }),
+        title: match matches.value_of("title") {
+            Some(title) => title.to_string(),
+            None => prompt::input("Issue title", None)?,
+        },
+        body: match matches.value_of("body") {
+            Some(body) => body.to_string(),
+            None => prompt::input_optional("Issue description")?,
+        },
         labels: matches
             .values_of("label")
             .map(|vals| vals.map(|v| v.to_string()).collect())
-            .unwrap_or_else(Vec::new),
+            .unwrap_or_else(|| config.project.issue.labels.clone()),
     };
 
-    match create_github_issue(&config, &repo_name, &issue) {
-        Ok(url) => println!("{} {}", "Issue created:".green(), url),
-        Err(e) => println!("{} {}", "Failed to create issue:".red(), e),
+    let created = create_github_issue(&config, &repo_name, &issue)
+        .map_err(|e| CliError::github("Failed to create issue:", e))?;
+    say!("{} {}", "Issue created:".green(), created.html_url);
+
+    Ok(json!({
+        "repository": repo_name,
+        "number": created.number,
+        "html_url": created.html_url,
+    }))
+}
+
+fn pr_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+    let config = require_config()?;
+
+    let repo_name = get_origin()
+        .map_err(|e| CliError::git("Failed to determine repository name:", e))?
+        .full_name();
+
+    let current_branch =
+        get_current_branch().map_err(|e| CliError::git("Failed to get current branch:", e))?;
+
+    // base branch from .git_mate.toml, then the configured default branch
+    let default_base = config
+        .project
+        .pr
+        .base
+        .clone()
+        .or_else(|| config.default_branch.clone())
+        .unwrap_or_else(|| "main".to_string());
+
+    // get title
+    let title = match matches.value_of("title") {
+        Some(title) => title.to_string(),
+        None => prompt::input(
+            "Pull request title",
+            Some(format!(
+                "Merge {} into {}",
+                current_branch,
+                matches.value_of("base").unwrap_or(&default_base)
+            )),
+        )?,
+    };
+
+    // get body
+    let body = match matches.value_of("body") {
+        Some(body) => body.to_string(),
+        None => prompt::input_optional("Pull request description")?,
+    };
+
+    // get base branch
+    let base = match matches.value_of("base") {
+        Some(base) => base.to_string(),
+        None => prompt::input("Base branch (target)", Some(default_base))?,
+    };
+
+    // get head branch
+    let head = match matches.value_of("head") {
+        Some(head) => head.to_string(),
+        None => prompt::input("Head branch (source)", Some(current_branch.clone()))?,
+    };
+
+    // push the current branch first to ensure it's on GitHub
+    match run_command("git", &["push", "--set-upstream", "origin", &head]) {
+        Ok(_) => say!("{} {}", "Pushed branch to GitHub:".green(), head),
+        Err(e) => {
+            say!("{} {}", "Failed to push branch:".red(), e);
+            say!("{}", "Continuing to create PR anyway...".yellow());
+        }
+    }
+
+    let created = create_github_pr(&config, &repo_name, &title, &body, &base, &head)
+        .map_err(|e| CliError::github("Failed to create pull request:", e))?;
+    say!("{} {}", "Pull request created:".green(), created.html_url);
+
+    // apply the project's default PR labels
+    let labels = &config.project.pr.labels;
+    if !labels.is_empty() {
+        match add_labels(&config, &repo_name, created.number, labels) {
+            Ok(_) => say!("{} {}", "Added labels:".green(), labels.join(", ")),
+            Err(e) => say!("{} {}", "Failed to add labels:".yellow(), e),
+        }
     }
+
+    Ok(json!({
+        "repository": repo_name,
+        "number": created.number,
+        "html_url": created.html_url,
+        "base": base,
+        "head": head,
+        "labels": labels,
+    }))
 }
 
-fn pr_command(matches: &ArgMatches) {
-    if !is_git_repository() {
-        println!(
-            "{}",
-            "Not a git repository. Run 'git_mate init' first.".red()
+fn workflow_command(matches: &ArgMatches) -> CommandResult {
+    match matches.subcommand() {
+        ("lint", Some(_)) => return workflow_lint_command(),
+        ("runs", Some(sub_matches)) => return workflow_runs_command(sub_matches),
+        ("run", Some(sub_matches)) => return workflow_run_command(sub_matches),
+        ("watch", Some(sub_matches)) => return workflow_watch_command(sub_matches),
+        ("logs", Some(sub_matches)) => return workflow_logs_command(sub_matches),
+        ("rerun", Some(sub_matches)) => return workflow_rerun_command(sub_matches),
+        ("cancel", Some(sub_matches)) => return workflow_cancel_command(sub_matches),
+        _ => {}
+    }
+    let project = load_project_config().map_err(CliError::config)?;
+
+    let workflow_type = match matches.value_of("type") {
+        Some(workflow_type) => workflow_type.to_string(),
+        None => {
+            // built-in workflows, then the user's and the project's templates
+            let templates = workflow::templates(&project).map_err(CliError::config)?;
+            let mut names: Vec<&str> = workflow::BUILTINS
+                .iter()
+                .map(|(name, _)| *name)
+                .filter(|name| !templates.iter().any(|t| t.name == *name))
+                .collect();
+            let mut options: Vec<String> = workflow::BUILTINS
+                .iter()
+                .filter(|(name, _)| names.contains(name))
+                .map(|(_, label)| label.to_string())
+                .collect();
+            for template in &templates {
+                names.push(&template.name);
+                options.push(format!("{} ({})", template.name, template.description));
+            }
+
+            // preselect the project's workflow type
+            let default = match project.workflow.workflow_type.as_deref() {
+                Some(configured) => {
+                    names.iter().position(|t| *t == configured).ok_or_else(|| {
+                        CliError::config(format!(
+                            "Unknown workflow type '{}' in {}",
+                            configured,
+                            project::PROJECT_CONFIG_FILE
+                        ))
+                    })?
+                }
+                None => 0,
+            };
+            names[prompt::select("Select a workflow type", &options, default)?].to_string()
+        }
+    };
+
+    let config = config::load_effective_config().map_err(CliError::config)?;
+    let path = setup_workflow(&workflow_type, &config, matches.is_present("force"))?;
+
+    Ok(json!({ "type": workflow_type, "path": path }))
+}
+
+fn workflow_lint_command() -> CommandResult {
+    let dir = Path::new(".github").join("workflows");
+    if !dir.is_dir() {
+        return Err(CliError::new(
+            ErrorKind::NotFound,
+            format!("There is no {} directory here.", dir.display()),
+        ));
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .map_err(|e| CliError::general(format!("Failed to read {}: {}", dir.display(), e)))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "yml" || extension == "yaml")
+        })
+        .collect();
+    paths.sort();
+
+    let mut files = Vec::new();
+    let mut invalid = 0;
+    for path in &paths {
+        let errors = match fs::read_to_string(path) {
+            Ok(contents) => workflow::validate(&contents),
+            Err(e) => vec![format!("failed to read: {}", e)],
+        };
+        if errors.is_empty() {
+            say!("{} {}", "ok".green(), path.display());
+        } else {
+            invalid += 1;
+            say!("{} {}", "invalid".red(), path.display());
+            for error in &errors {
+                say!("  {}", error);
+            }
+        }
+        files.push(json!({ "path": path.display().to_string(), "errors": errors }));
+    }
+
+    if invalid > 0 {
+        return Err(CliError::new(
+            ErrorKind::Validation,
+            format!("{} of {} workflow(s) have errors.", invalid, paths.len()),
+        ));
+    }
+    Ok(json!({ "files": files }))
+}
+
+fn workflow_runs_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+    let config = require_config()?;
+    let repo_name = origin_repo_name()?;
+
+    let limit: u32 = matches
+        .value_of("limit")
+        .and_then(|limit| limit.parse().ok())
+        .filter(|limit| (1..=100).contains(limit))
+        .ok_or_else(|| CliError::usage("--limit must be a number from 1 to 100"))?;
+    let branch = if matches.is_present("all-branches") {
+        None
+    } else {
+        Some(match matches.value_of("branch") {
+            Some(branch) => branch.to_string(),
+            None => get_current_branch()
+                .map_err(|e| CliError::git("Failed to get current branch:", e))?,
+        })
+    };
+
+    let runs = GitHubClient::new(&config)
+        .and_then(|client| {
+            client.workflow_runs(
+                &repo_name,
+                matches.value_of("workflow"),
+                branch.as_deref(),
+                limit,
+            )
+        })
+        .map_err(|e| CliError::github("Failed to list workflow runs:", e))?;
+
+    if runs.is_empty() {
+        match &branch {
+            Some(branch) => say!("{} {}", "No workflow runs for branch".yellow(), branch),
+            None => say!("{}", "No workflow runs yet.".yellow()),
+        }
+    }
+    for run in &runs {
+        say!(
+            "{} {} {} {} {} {}",
+            run_state(run.status.as_deref(), run.conclusion.as_deref()),
+            format!("{:>8}", run.id).dimmed(),
+            run.name.as_deref().unwrap_or("").bold(),
+            run.display_title.as_deref().unwrap_or(""),
+            format!(
+                "({}, {}, {})",
+                run.event,
+                run.head_branch.as_deref().unwrap_or("-"),
+                run.created_at
+            )
+            .dimmed(),
+            run.html_url
         );
-        return;
     }
 
-    let config = match load_config() {
-        Ok(config) => config,
-        Err(_) => {
-            println!(
-                "{}",
-                "No GitHub configuration found. Please run 'git_mate config' first.".red()
-            );
-            return;
+    Ok(json!({ "repository": repo_name, "branch": branch, "runs": runs }))
+}
+
+fn workflow_run_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+    let config = require_config()?;
+    let repo_name = origin_repo_name()?;
+
+    let workflow = matches.value_of("workflow").unwrap_or_default();
+    let git_ref = match matches.value_of("ref") {
+        Some(git_ref) => git_ref.to_string(),
+        None => {
+            get_current_branch().map_err(|e| CliError::git("Failed to get current branch:", e))?
         }
     };
+    let mut inputs = Map::new();
+    for input in matches.values_of("input").into_iter().flatten() {
+        match input.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                inputs.insert(key.trim().to_string(), json!(value));
+            }
+            _ => {
+                return Err(CliError::usage(format!(
+                    "Invalid input '{}'. Use --input key=value.",
+                    input
+                )))
+            }
+        }
+    }
 
-    let repo_name = match get_repo_name_from_remote() {
-        Ok(name) => name,
-        Err(e) => {
-            println!("{} {}", "Failed to determine repository name:".red(), e);
-            return;
+    let _: Value = GitHubClient::new(&config)
+        .and_then(|client| {
+            client.post(
+                &format!(
+                    "/repos/{}/actions/workflows/{}/dispatches",
+                    repo_name, workflow
+                ),
+                &json!({ "ref": git_ref, "inputs": inputs }),
+            )
+        })
+        .map_err(|e| CliError::github("Failed to trigger workflow:", e))?;
+    say!(
+        "{} {} on {}",
+        "Triggered workflow".green(),
+        workflow,
+        git_ref
+    );
+    say!(
+        "{}",
+        "Follow it with 'git_mate workflow watch' once it has started.".cyan()
+    );
+
+    Ok(json!({
+        "repository": repo_name,
+        "workflow": workflow,
+        "ref": git_ref,
+        "inputs": inputs,
+    }))
+}
+
+fn workflow_watch_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+    let config = require_config()?;
+    let repo_name = origin_repo_name()?;
+    let interval: u64 = matches
+        .value_of("interval")
+        .and_then(|interval| interval.parse().ok())
+        .filter(|interval| *interval > 0)
+        .ok_or_else(|| CliError::usage("--interval must be a positive number of seconds"))?;
+    let client = GitHubClient::new(&config)
+        .map_err(|e| CliError::github("Failed to connect to GitHub:", e))?;
+
+    let id = match matches.value_of("run-id") {
+        Some(id) => parse_run_id(id)?,
+        None => {
+            let branch = get_current_branch()
+                .map_err(|e| CliError::git("Failed to get current branch:", e))?;
+            client
+                .workflow_runs(&repo_name, None, Some(&branch), 1)
+                .map_err(|e| CliError::github("Failed to list workflow runs:", e))?
+                .first()
+                .map(|run| run.id)
+                .ok_or_else(|| {
+                    CliError::new(
+                        ErrorKind::NotFound,
+                        format!("No workflow runs for branch {}.", branch),
+                    )
+                })?
         }
     };
 
-    let current_branch = match get_current_branch() {
-        Ok(branch) => branch,
-        Err(e) => {
-            println!("{} {}", "Failed to get current branch:".red(), e);
-            return;
+    // poll until the run completes, showing how many jobs are done
+    let spinner = output::spinner("Waiting for the workflow run...");
+    let (run, jobs) = loop {
+        let run = client
+            .workflow_run(&repo_name, id)
+            .map_err(|e| CliError::github("Failed to get workflow run:", e))?;
+        let jobs = client
+            .run_jobs(&repo_name, id)
+            .map_err(|e| CliError::github("Failed to get workflow jobs:", e))?;
+        let status = run.status.clone().unwrap_or_default();
+        if status == "completed" {
+            break (run, jobs);
         }
+        let done = jobs.iter().filter(|job| job.status == "completed").count();
+        spinner.set_message(format!(
+            "{} #{}: {} ({} of {} jobs done)",
+            run.name.as_deref().unwrap_or("Workflow"),
+            run.run_number,
+            status.replace('_', " "),
+            done,
+            jobs.len()
+        ));
+        thread::sleep(Duration::from_secs(interval));
     };
+    spinner.finish_and_clear();
 
-    // get title
-    let title = matches
-        .value_of("title")
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| {
-            Input::new()
-                .with_prompt("Pull request title")
-                .default(format!("Merge {} into main", current_branch))
-                .interact()
-                .unwrap_or_else(|_| format!("Merge {} into main", current_branch))
-        });
+    for job in &jobs {
+        say!(
+            "{} {}",
+            run_state(Some(&job.status), job.conclusion.as_deref()),
+            job.name
+        );
+    }
+    let conclusion = run.conclusion.clone().unwrap_or_default();
+    let summary = format!(
+        "{} #{} finished: {}",
+        run.name.as_deref().unwrap_or("Workflow"),
+        run.run_number,
+        conclusion
+    );
+    if conclusion != "success" {
+        return Err(CliError::general(format!("{}\n{}", summary, run.html_url)));
+    }
+    say!("{} {}", summary.green(), run.html_url);
 
-    // get body
-    let body = matches
-        .value_of("body")
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| {
-            Input::new()
-                .with_prompt("Pull request description")
-                .interact()
-                .unwrap_or_else(|_| "".to_string())
-        });
+    Ok(json!({ "repository": repo_name, "run": run, "jobs": jobs }))
+}
 
-    // get base branch
-    let base = matches
-        .value_of("base")
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| {
-            let default = "main".to_string();
-            Input::new()
-                .with_prompt("Base branch (target)")
-                .default(default)
-                .interact()
-                .unwrap_or_else(|_| "main".to_string())
-        });
+fn workflow_logs_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+    let config = require_config()?;
+    let repo_name = origin_repo_name()?;
+    let id = parse_run_id(matches.value_of("run-id").unwrap_or_default())?;
+    let failed_only = matches.is_present("failed");
+    let client = GitHubClient::new(&config)
+        .map_err(|e| CliError::github("Failed to connect to GitHub:", e))?;
+
+    let jobs = client
+        .run_jobs(&repo_name, id)
+        .map_err(|e| CliError::github("Failed to get workflow jobs:", e))?;
+    if jobs.iter().any(|job| job.status != "completed") {
+        return Err(CliError::general(format!(
+            "Run {} is still in progress; its logs are available once it finishes.\n\
+             Use `git_mate workflow watch {}` to wait for it.",
+            id, id
+        )));
+    }
 
-    // get head branch
-    let head = matches
-        .value_of("head")
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| {
-            Input::new()
-                .with_prompt("Head branch (source)")
-                .default(current_branch.clone())
-                .interact()
-                .unwrap_or_else(|_| current_branch.clone())
-        });
+    let spinner = output::spinner("Downloading logs...");
+    let archive = client
+        .run_logs(&repo_name, id)
+        .map_err(|e| CliError::github("Failed to download logs:", e))?;
+    spinner.finish_and_clear();
+    let files = logs::unzip(&archive)
+        .map_err(|e| CliError::general(format!("Failed to read the log archive: {}", e)))?;
+
+    let mut jobs = logs::job_logs(&files, jobs);
+    if failed_only {
+        for job in &mut jobs {
+            job.steps.retain(|step| step.failed());
+        }
+        jobs.retain(|job| !job.steps.is_empty());
+        if jobs.is_empty() {
+            say!("{} {}", "No failed steps in run".green(), id);
+        }
+    }
 
-    // push the current branch first to ensure it's on GitHub
-    match run_command("git", &["push", "--set-upstream", "origin", &head]) {
-        Ok(_) => println!("{} {}", "Pushed branch to GitHub:".green(), head),
-        Err(e) => {
-            println!("{} {}", "Failed to push branch:".red(), e);
-            println!("{}", "Continuing to create PR anyway...".yellow());
+    for job in &jobs {
+        say!(
+            "{} {}",
+            run_state(Some(&job.status), job.conclusion.as_deref()),
+            job.name.bold()
+        );
+        for step in &job.steps {
+            let name = format!("{}. {}", step.number, step.name);
+            let name = if step.failed() {
+                name.red().bold()
+            } else {
+                name.normal()
+            };
+            say!(
+                "  {} {}",
+                run_state(Some(&step.status), step.conclusion.as_deref()),
+                name
+            );
+            for line in &step.lines {
+                if step.failed() {
+                    say!("    {}", line.red());
+                } else {
+                    say!("    {}", line);
+                }
+            }
         }
+        say!();
     }
 
-    match create_github_pr(&config, &repo_name, &title, &body, &base, &head) {
-        Ok(url) => println!("{} {}", "Pull request created:".green(), url),
-        Err(e) => println!("{} {}", "Failed to create pull request:".red(), e),
+    Ok(json!({
+        "repository": repo_name,
+        "run_id": id,
+        "failed_only": failed_only,
+        "jobs": jobs,
+    }))
+}
+
+fn workflow_rerun_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+    let config = require_config()?;
+    let repo_name = origin_repo_name()?;
+    let id = parse_run_id(matches.value_of("run-id").unwrap_or_default())?;
+    let failed = matches.is_present("failed");
+
+    let action = if failed { "rerun-failed-jobs" } else { "rerun" };
+    let _: Value = GitHubClient::new(&config)
+        .and_then(|client| {
+            client.post(
+                &format!("/repos/{}/actions/runs/{}/{}", repo_name, id, action),
+                &json!({}),
+            )
+        })
+        .map_err(|e| CliError::github("Failed to re-run workflow run:", e))?;
+    if failed {
+        say!("{} {}", "Re-running failed jobs of run".green(), id);
+    } else {
+        say!("{} {}", "Re-running run".green(), id);
     }
+
+    Ok(json!({ "repository": repo_name, "run_id": id, "failed_only": failed }))
+}
+
+fn workflow_cancel_command(matches: &ArgMatches) -> CommandResult {
+    require_git_repository()?;
+    let config = require_config()?;
+    let repo_name = origin_repo_name()?;
+    let id = parse_run_id(matches.value_of("run-id").unwrap_or_default())?;
+
+    let _: Value = GitHubClient::new(&config)
+        .and_then(|client| {
+            client.post(
+                &format!("/repos/{}/actions/runs/{}/cancel", repo_name, id),
+                &json!({}),
+            )
+        })
+        .map_err(|e| CliError::github("Failed to cancel workflow run:", e))?;
+    say!("{} {}", "Cancelling run".green(), id);
+
+    Ok(json!({ "repository": repo_name, "run_id": id }))
 }
 
-fn workflow_command(matches: &ArgMatches) {
-    let workflow_type = matches.value_of("type").unwrap_or_else(|| {
-        let options = vec!["Continuous Integration", "Deployment", "Custom"];
-        let selection = Select::new()
-            .with_prompt("Select a workflow type")
-            .items(&options)
-            .default(0)
-            .interact()
-            .unwrap_or(0);
+fn origin_repo_name() -> Result<String, CliError> {
+    get_origin()
+        .map(|origin| origin.full_name())
+        .map_err(|e| CliError::git("Failed to determine repository name:", e))
+}
 
-        match selection {
-            0 => "ci",
-            1 => "deploy",
-            _ => "custom",
-        }
-    });
+fn parse_run_id(id: &str) -> Result<u64, CliError> {
+    id.parse()
+        .map_err(|_| CliError::usage(format!("Invalid run ID '{}'", id)))
+}
 
-    setup_workflow(workflow_type);
+// a colored mark and word for a run's or job's state, padded to line up
+fn run_state(status: Option<&str>, conclusion: Option<&str>) -> ColoredString {
+    let (mark, word) = match (status.unwrap_or(""), conclusion.unwrap_or("")) {
+        ("completed", conclusion) => match conclusion {
+            "success" => ("✓", conclusion),
+            "failure" | "timed_out" | "startup_failure" => ("✗", conclusion),
+            _ => ("-", conclusion),
+        },
+        (status, _) => ("●", status),
+    };
+    let text = format!("{} {:<11}", mark, word.replace('_', " "));
+    match mark {
+        "✓" => text.green(),
+        "✗" => text.red(),
+        "-" => text.dimmed(),
+        _ => text.yellow(),
+    }
 }
 
-fn get_repository_info(matches: &ArgMatches) -> RepoInfo {
+fn get_repository_info(matches: &ArgMatches, config: &Config) -> Result<RepoInfo, CliError> {
     let current_dir = Path::new(".")
         .canonicalize()
         .unwrap_or_else(|_| Path::new(".").to_path_buf());
@@ -801,313 +2465,649 @@ fn get_repository_info(matches: &ArgMatches) -> RepoInfo {
 
     let name = match matches.value_of("name") {
         Some(name) => name.to_string(),
-        None => Input::new()
-            .with_prompt("Repository name")
-            .default(default_name)
-            .interact()
-            .unwrap(),
+        None => prompt::input("Repository name", Some(default_name))?,
     };
 
+    let owner = select_owner(matches, config)?;
+
     let description = match matches.value_of("description") {
         Some(desc) => desc.to_string(),
-        None => Input::new()
-            .with_prompt("Repository description")
-            .default(String::from("Created with git_mate"))
-            .interact()
-            .unwrap(),
+        None => prompt::input(
+            "Repository description",
+            Some(String::from("Created with git_mate")),
+        )?,
     };
 
-    let private = if matches.is_present("private") {
-        true
+    let visibility = if matches.is_present("private") {
+        "private".to_string()
+    } else if let Some(visibility) = matches.value_of("visibility") {
+        if visibility == "internal" && owner.is_none() {
+            return Err(CliError::usage(
+                "Internal visibility is only available for organization repositories",
+            ));
+        }
+        visibility.to_string()
     } else {
-        let options = vec!["Public", "Private"];
-        let selection = Select::new()
-            .with_prompt("Repository visibility")
-            .default(0)
-            .items(&options)
-            .interact()
-            .unwrap_or(0);
-        selection == 1
+        let mut options = vec!["Public", "Private"];
+        if owner.is_some() {
+            options.push("Internal");
+        }
+        options[prompt::select("Repository visibility", &options, 0)?].to_lowercase()
     };
 
-    let license = matches.value_of("license").map(|s| s.to_string());
+    let settings = RepoSettings::from_matches(matches)?;
 
-    RepoInfo {
+    let template = match matches.value_of("template") {
+        Some(template) => {
+            let template = template.trim().trim_matches('/');
+            match template.split_once('/') {
+                Some((owner, repo)) if !owner.is_empty() && !repo.contains('/') => {
+                    Some(template.to_string())
+                }
+                _ => {
+                    return Err(CliError::usage(format!(
+                        "Invalid template '{}'. Use the form owner/repo.",
+                        template
+                    )))
+                }
+            }
+        }
+        None => None,
+    };
+
+    Ok(RepoInfo {
         name,
+        owner,
+        account: None,
         description,
-        private,
-        license,
+        visibility,
+        template,
+        settings,
+    })
+}
+
+// pick the account to create the repository under: --org, then the
+// configured default owner, then a choice between the user's organizations
+fn select_owner(matches: &ArgMatches, config: &Config) -> Result<Option<String>, CliError> {
+    let personal = |owner: &str| owner.eq_ignore_ascii_case(&config.username);
+
+    if let Some(owner) = matches
+        .value_of("org")
+        .or(config.default_owner.as_deref())
+        .map(str::trim)
+        .filter(|owner| !owner.is_empty())
+    {
+        return Ok(Some(owner.to_string()).filter(|owner| !personal(owner)));
     }
+    if !prompt::is_interactive() {
+        return Ok(None);
+    }
+
+    let orgs = match GitHubClient::new(config).and_then(|client| client.organizations()) {
+        Ok(orgs) => orgs,
+        Err(e) => {
+            say!(
+                "{} {}",
+                "Could not list your organizations (the token may lack the 'read:org' scope):"
+                    .yellow(),
+                e
+            );
+            return Ok(None);
+        }
+    };
+    if orgs.is_empty() {
+        return Ok(None);
+    }
+
+    let account = if config.username.is_empty() {
+        "Personal account".to_string()
+    } else {
+        format!("{} (personal account)", config.username)
+    };
+    let options: Vec<String> = std::iter::once(account)
+        .chain(orgs.iter().map(|org| org.login.clone()))
+        .collect();
+    let selection = prompt::select("Repository owner", &options, 0)?;
+    Ok(selection
+        .checked_sub(1)
+        .map(|index| orgs[index].login.clone()))
 }
 
-fn create_github_repo(config: &Config, repo_info: &RepoInfo) -> Result<String, String> {
-    let client = Client::new();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        ACCEPT,
-        HeaderValue::from_static("application/vnd.github.v3+json"),
-    );
-    headers.insert(USER_AGENT, HeaderValue::from_static("Git-Mate-CLI"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("token {}", config.github_token))
-            .map_err(|e| e.to_string())?,
-    );
+fn create_github_repo(config: &Config, repo_info: &RepoInfo) -> Result<CreatedRepo, GitHubError> {
+    let client = GitHubClient::new(config)?;
 
-    let repo_data = json!({
+    let mut repo_data = json!({
         "name": repo_info.name,
         "description": repo_info.description,
-        "private": repo_info.private,
+        "private": repo_info.visibility != "public",
         "auto_init": false,
-        "license_template": repo_info.license
     });
+    if repo_info.visibility == "internal" {
+        repo_data["visibility"] = json!("internal");
+    }
+    if let Value::Object(fields) = &mut repo_data {
+        fields.extend(repo_info.settings.fields());
+    }
 
-    let res = client
-        .post("https://api.github.com/user/repos")
-        .headers(headers)
-        .json(&repo_data)
-        .send()
-        .map_err(|e| e.to_string())?;
-
-    if res.status().is_success() {
-        let json: Value = res.json().map_err(|e| e.to_string())?;
-        if let Some(ssh_url) = json["ssh_url"].as_str() {
-            Ok(ssh_url.to_string())
-        } else if let Some(clone_url) = json["clone_url"].as_str() {
-            Ok(clone_url.to_string())
-        } else {
-            Err("Failed to get repository URL from GitHub response".to_string())
+    let path = match &repo_info.owner {
+        Some(org) => format!("/orgs/{}/repos", org),
+        None => "/user/repos".to_string(),
+    };
+    let json: Value = client.post(&path, &repo_data)?;
+    created_repo(&client, &json, &new_repo_name(config, repo_info))
+}
+
+// look for an existing origin and for a repository with the target name,
+// and let the user link to it, pick another name or abort. Returns the
+// repository to link to, if any.
+fn check_existing_repo(
+    config: &Config,
+    repo_info: &mut RepoInfo,
+    protocol: Option<&str>,
+) -> Result<Option<CreatedRepo>, CliError> {
+    let client = GitHubClient::new(config)
+        .map_err(|e| CliError::github("Failed to check for an existing repository:", e))?;
+    let lookup = |full_name: &str| {
+        find_github_repo(&client, full_name)
+            .map_err(|e| CliError::github("Failed to check for an existing repository:", e))
+    };
+
+    if let Ok(url) = get_remote_url("origin") {
+        say!("{} {}", "Remote 'origin' already exists:".yellow(), url);
+        // only a remote on the configured host can be the repository we'd
+        // find there; ports are left out since SSH remotes don't carry them
+        let hostname = |host: &str| host.split(':').next().unwrap_or(host).to_lowercase();
+        let web_host = hostname(config::url_host(&config.web_url()));
+        let existing = match parse_remote_url(&url) {
+            Some(remote) if hostname(&remote.host) == web_host => lookup(&remote.full_name())?,
+            _ => None,
+        };
+        let replace = "Create a new repository and replace origin".to_string();
+        let abort = "Abort".to_string();
+        match existing {
+            Some(mut repo) => {
+                let options = [format!("Link to {}", repo.full_name), replace, abort];
+                match prompt::select("What should init do?", &options, 2)? {
+                    0 => {
+                        // keep the remote URL the user set up
+                        repo.remote_url = url;
+                        return Ok(Some(repo));
+                    }
+                    1 => {}
+                    _ => {
+                        return Err(CliError::cancelled(
+                            "Aborted: remote 'origin' already exists.",
+                        ))
+                    }
+                }
+            }
+            None => {
+                let options = [replace, abort];
+                if prompt::select("What should init do?", &options, 1)? != 0 {
+                    return Err(CliError::cancelled(
+                        "Aborted: remote 'origin' already exists.",
+                    ));
+                }
+            }
+        }
+    }
+
+    loop {
+        let owner = match &repo_info.owner {
+            Some(owner) => owner.clone(),
+            None if !config.username.is_empty() => config.username.clone(),
+            None => match &repo_info.account {
+                Some(account) => account.clone(),
+                None => {
+                    // kept so the new repository's name is right even in a
+                    // dry run, e.g. with only GITHUB_TOKEN set
+                    let login = client
+                        .current_user()
+                        .map_err(|e| {
+                            CliError::github("Failed to check for an existing repository:", e)
+                        })?
+                        .login;
+                    repo_info.account = Some(login.clone());
+                    login
+                }
+            },
+        };
+        let full_name = format!("{}/{}", owner, repo_info.name);
+        let mut repo = match lookup(&full_name)? {
+            Some(repo) => repo,
+            None => return Ok(None),
+        };
+
+        say!(
+            "{} {}",
+            "Repository already exists on GitHub:".yellow(),
+            repo.html_url
+        );
+        let options = [
+            format!("Link to {}", full_name),
+            "Choose a different name".to_string(),
+            "Abort".to_string(),
+        ];
+        match prompt::select("What should init do?", &options, 2)? {
+            0 => {
+                choose_remote_url(config, protocol, &mut repo);
+                return Ok(Some(repo));
+            }
+            1 => repo_info.name = prompt::input("Repository name", None)?,
+            _ => {
+                return Err(CliError::cancelled(format!(
+                    "Aborted: repository {} already exists.",
+                    full_name
+                )))
+            }
         }
-    } else {
-        let status = res.status();
-        let text = res.text().unwrap_or_else(|_| "Unknown error".to_string());
-        Err(format!("GitHub API error ({}): {}", status, text))
     }
 }
 
-fn get_config_path() -> Result<std::path::PathBuf, String> {
-    let home = home_dir().ok_or_else(|| "Could not find home directory".to_string())?;
-    let config_dir = home.join(CONFIG_DIR);
-    if !config_dir.exists() {
-        fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
+// the repository if it exists, `None` if GitHub doesn't know it
+fn find_github_repo(
+    client: &GitHubClient,
+    full_name: &str,
+) -> Result<Option<CreatedRepo>, GitHubError> {
+    match client.get::<Value>(&format!("/repos/{}", full_name)) {
+        Ok(json) => created_repo(client, &json, full_name).map(Some),
+        Err(GitHubError::NotFound { .. }) => Ok(None),
+        Err(e) => Err(e),
     }
-    Ok(config_dir.join(CONFIG_FILE))
 }
 
-fn load_config() -> Result<Config, String> {
-    let config_path = get_config_path()?;
-    if !config_path.exists() {
-        return Err("Config file does not exist".to_string());
+fn generate_github_repo(
+    config: &Config,
+    template: &str,
+    repo_info: &RepoInfo,
+) -> Result<CreatedRepo, GitHubError> {
+    let client = GitHubClient::new(config)?;
+
+    let mut repo_data = json!({
+        "name": repo_info.name,
+        "description": repo_info.description,
+        "private": repo_info.visibility != "public",
+        "include_all_branches": false,
+    });
+    if let Some(owner) = &repo_info.owner {
+        repo_data["owner"] = json!(owner);
     }
-    let config_str = fs::read_to_string(config_path).map_err(|e| e.to_string())?;
-    serde_json::from_str(&config_str).map_err(|e| e.to_string())
+
+    let json: Value = client.post(&format!("/repos/{}/generate", template), &repo_data)?;
+    created_repo(&client, &json, &new_repo_name(config, repo_info))
 }
 
-fn save_config(config: &Config) -> Result<(), String> {
-    let config_path = get_config_path()?;
-    let config_str = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
-    fs::write(config_path, config_str).map_err(|e| e.to_string())
+fn new_repo_name(config: &Config, repo_info: &RepoInfo) -> String {
+    format!(
+        "{}/{}",
+        repo_info
+            .owner
+            .as_deref()
+            .or(repo_info.account.as_deref())
+            .unwrap_or(&config.username),
+        repo_info.name
+    )
 }
 
-fn run_command(command: &str, args: &[&str]) -> Result<Output, String> {
-    Command::new(command)
-        .args(args)
-        .output()
-        .map_err(|e| e.to_string())
-        .and_then(|output| {
-            if output.status.success() {
-                Ok(output)
-            } else {
-                let error = str::from_utf8(&output.stderr)
-                    .unwrap_or("Unknown error")
-                    .trim();
-                Err(error.to_string())
+// `full_name` is used when the response lacks one
+fn created_repo(
+    client: &GitHubClient,
+    json: &Value,
+    full_name: &str,
+) -> Result<CreatedRepo, GitHubError> {
+    let full_name = json["full_name"].as_str().unwrap_or(full_name).to_string();
+    let mut ssh_url = json["ssh_url"].as_str().map(|url| url.to_string());
+    let mut clone_url = json["clone_url"].as_str().map(|url| url.to_string());
+    // nothing was created in a dry run; show the URLs GitHub would use
+    if dry_run::is_dry_run() {
+        let host = config::url_host(client.web_url());
+        ssh_url = Some(format!("git@{}:{}.git", host, full_name));
+        clone_url = Some(format!("{}/{}.git", client.web_url(), full_name));
+    }
+    let remote_url = ssh_url
+        .clone()
+        .or_else(|| clone_url.clone())
+        .ok_or_else(|| GitHubError::Decode("missing repository URL".to_string()))?;
+    let html_url = match json["html_url"].as_str() {
+        Some(url) => url.to_string(),
+        None => format!("{}/{}", client.web_url(), full_name),
+    };
+    Ok(CreatedRepo {
+        full_name,
+        remote_url,
+        ssh_url,
+        clone_url,
+        html_url,
+        default_branch: json["default_branch"].as_str().map(|b| b.to_string()),
+    })
+}
+
+// point `repo.remote_url` at the SSH or HTTPS URL: --protocol, then the
+// git_protocol setting, then SSH if it authenticates and HTTPS otherwise
+fn choose_remote_url(config: &Config, protocol: Option<&str>, repo: &mut CreatedRepo) {
+    let ssh_host = repo
+        .ssh_url
+        .as_deref()
+        .and_then(parse_remote_url)
+        .map(|remote| remote.host);
+
+    let use_ssh = match (protocol.or(config.git_protocol.as_deref()), &ssh_host) {
+        (Some(protocol), _) => protocol == "ssh",
+        (None, Some(host)) => {
+            let spinner = output::spinner(&format!("Checking SSH access to {}...", host));
+            let works = ssh_auth_works(host);
+            spinner.finish_and_clear();
+            if !works {
+                say!(
+                    "{} SSH authentication to {} failed, using HTTPS.",
+                    "Note:".cyan(),
+                    host
+                );
+                if !has_credential_helper() {
+                    say!(
+                        "{} neither SSH keys nor a git credential helper are set up for {}, so pushing will fail. Add an SSH key at {}/settings/keys or install a credential helper such as Git Credential Manager, then run 'git_mate init --resume' if the push fails.",
+                        "Warning:".yellow(),
+                        host,
+                        config.web_url()
+                    );
+                }
             }
-        })
+            works
+        }
+        (None, None) => false,
+    };
+
+    let url = if use_ssh {
+        repo.ssh_url.clone().or_else(|| repo.clone_url.clone())
+    } else {
+        repo.clone_url.clone().or_else(|| repo.ssh_url.clone())
+    };
+    if let Some(url) = url {
+        repo.remote_url = url;
+    }
 }
 
-fn is_git_repository() -> bool {
-    Path::new(".git").exists()
+// suggest switching protocols when the push fails to authenticate
+fn push_guidance(repo: &CreatedRepo, error: String) -> String {
+    let auth_failed = [
+        "Permission denied",
+        "Could not read from remote repository",
+        "Host key verification failed",
+        "Authentication failed",
+        "could not read Username",
+    ]
+    .iter()
+    .any(|needle| error.contains(needle));
+    let other_url = if repo.ssh_url.as_ref() == Some(&repo.remote_url) {
+        repo.clone_url.as_ref()
+    } else {
+        repo.ssh_url.as_ref()
+    };
+
+    match other_url {
+        Some(url) if auth_failed => format!(
+            "{}\nCheck your SSH keys or git credentials for this host, or switch protocols with 'git remote set-url origin {}'.",
+            error, url
+        ),
+        _ => error,
+    }
 }
 
-fn get_current_branch() -> Result<String, String> {
-    let output = run_command("git", &["rev-parse", "--abbrev-ref", "HEAD"])?;
-    let branch = str::from_utf8(&output.stdout)
-        .map_err(|e| e.to_string())?
-        .trim()
-        .to_string();
-    Ok(branch)
+fn update_repo(
+    config: &Config,
+    repo_name: &str,
+    fields: &Map<String, Value>,
+) -> Result<(), GitHubError> {
+    if !fields.is_empty() {
+        let client = GitHubClient::new(config)?;
+        let _: Value = client.patch(&format!("/repos/{}", repo_name), fields)?;
+    }
+    Ok(())
 }
 
-fn get_repo_name_from_remote() -> Result<String, String> {
-    // try to get the remote URL
-    let output = run_command("git", &["remote", "get-url", "origin"])?;
-    let remote_url = str::from_utf8(&output.stdout)
-        .map_err(|e| e.to_string())?
-        .trim();
+// bring the history of the GitHub repository into the local repository and
+// return whether there was any. Generation from a template is asynchronous,
+// so with `wait` the branch is polled for; otherwise an empty repository
+// just has nothing to merge.
+fn merge_remote_history(repo: &CreatedRepo, source: &str, wait: bool) -> Result<bool, CliError> {
+    let branch = repo.default_branch.as_deref().unwrap_or("main");
+    let remote_branch = format!("origin/{}", branch);
+    let attempts = if wait { TEMPLATE_FETCH_ATTEMPTS } else { 1 };
+
+    let spinner = output::spinner(&format!("Fetching {}...", repo.full_name));
+    let mut attempt = 0;
+    let fetched = loop {
+        attempt += 1;
+        // in a dry run the fetch doesn't happen, so assume the branch is there
+        let result = run_command("git", &["fetch", "origin"]).map(|_| {
+            dry_run::is_dry_run()
+                || run_command("git", &["rev-parse", "--verify", "--quiet", &remote_branch]).is_ok()
+        });
+        if result == Ok(true) || attempt == attempts {
+            break result;
+        }
+        thread::sleep(Duration::from_secs(2));
+    };
+    spinner.finish_and_clear();
+    match fetched {
+        Ok(true) => {}
+        Ok(false) if !wait => return Ok(false),
+        Ok(false) => {
+            return Err(CliError::git(
+                "Failed to fetch the generated repository:",
+                format!("branch '{}' not found on origin", branch),
+            ))
+        }
+        Err(e) => return Err(CliError::git("Failed to fetch from origin:", e)),
+    }
 
-    // parse the repository name from the URL
-    // format could be:
-    // - git@github.com:username/repo.git
-    // - https://github.com/username/repo.git
+    // local commits are kept and merged with the remote history
+    let message = format!("Merge {}", source);
+    let args: &[&str] = if has_commits() {
+        &[
+            "merge",
+            "--allow-unrelated-histories",
+            "-m",
+            &message,
+            &remote_branch,
+        ]
+    } else {
+        &["merge", &remote_branch]
+    };
+    run_command("git", args).map_err(|e| CliError::git("Failed to merge remote history:", e))?;
+    say!("{} {}", "Merged files from".green(), source);
+    Ok(true)
+}
 
-    if remote_url.contains("github.com") {
-        let parts: Vec<&str> = if remote_url.starts_with("git@") {
-            // SSH format
-            remote_url.split(':').collect()
-        } else {
-            // HTTPS format
-            remote_url.split('/').collect()
-        };
+// offer the .gitignore template and CI workflow for the detected language,
+// for whatever the flags left out and the project doesn't have yet, then
+// the configured default license. Scripts only get the default license.
+fn propose_project_files(journal: &mut InitJournal, config: &Config) -> Result<(), CliError> {
+    let detected = detect_language(Path::new(".")).filter(|_| prompt::is_interactive());
+    if let Some((language, marker)) = detected {
+        say!(
+            "{} {} ({})",
+            "Detected language:".cyan(),
+            language.name.bold(),
+            marker
+        );
 
-        if parts.len() >= 2 {
-            let repo_part = parts[parts.len() - 1].replace(".git", "");
-            let username_part = parts[parts.len() - 2].replace("github.com:", "");
+        if journal.gitignore.is_none() && !Path::new(".gitignore").exists() {
+            match gitignore_template_for(language, config) {
+                Ok(Some(template)) => {
+                    if prompt::confirm(&format!("Add the {} .gitignore template?", template), true)?
+                    {
+                        journal.gitignore = Some(template);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => say!("{} {}", "Failed to fetch .gitignore templates:".yellow(), e),
+            }
+        }
 
-            return Ok(format!("{}/{}", username_part, repo_part));
+        let ci_workflow = Path::new(".github").join("workflows").join("ci.yml");
+        if journal.workflow.is_none()
+            && !ci_workflow.exists()
+            && prompt::confirm(&format!("Add a CI workflow for {}?", language.name), true)?
+        {
+            journal.workflow = Some("ci".to_string());
         }
     }
 
-    Err("Could not parse repository name from remote URL".to_string())
+    if journal.license.is_none() && !Path::new("LICENSE").exists() {
+        if let Some(license) = &config.default_license {
+            if prompt::confirm(&format!("Add the {} license?", license), true)? {
+                journal.license = Some(license.clone());
+            }
+        }
+    }
+    Ok(())
 }
 
-fn get_gitignore_templates(config: &Config) -> Result<Vec<String>, String> {
-    let client = Client::new();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        ACCEPT,
-        HeaderValue::from_static("application/vnd.github.v3+json"),
-    );
-    headers.insert(USER_AGENT, HeaderValue::from_static("Git-Mate-CLI"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("token {}", config.github_token))
-            .map_err(|e| e.to_string())?,
-    );
+// the name GitHub knows the language's .gitignore template by, if it has one
+fn gitignore_template_for(
+    language: &Language,
+    config: &Config,
+) -> Result<Option<String>, GitHubError> {
+    Ok(get_gitignore_templates(config)?
+        .into_iter()
+        .find(|template| template.eq_ignore_ascii_case(language.gitignore)))
+}
+
+fn setup_project_files(journal: &mut InitJournal, config: &Config) -> Result<(), CliError> {
+    // set up .gitignore if requested
+    if let Some(template) = journal.gitignore.clone() {
+        journal.record_file(".gitignore");
+        setup_gitignore(&template, config)?;
+    }
 
-    let res = client
-        .get(GITIGNORE_API_URL)
-        .headers(headers)
-        .send()
-        .map_err(|e| e.to_string())?;
+    // set up license if requested
+    if let Some(license) = journal.license.clone() {
+        journal.record_file("LICENSE");
+        let holder = license::copyright_holder(config, journal.holder.as_deref());
+        setup_license(&license, &holder, config)?;
+    }
+    Ok(())
+}
+
+fn set_topics(
+    config: &Config,
+    repo_name: &str,
+    settings: &RepoSettings,
+) -> Result<(), GitHubError> {
+    if let Some(body) = settings.topics_body() {
+        let client = GitHubClient::new(config)?;
+        let _: Value = client.put(&format!("/repos/{}/topics", repo_name), &body)?;
+    }
+    Ok(())
+}
 
-    if res.status().is_success() {
-        let templates: Vec<String> = res.json().map_err(|e| e.to_string())?;
-        Ok(templates)
+fn require_git_repository() -> Result<(), CliError> {
+    if is_git_repository() {
+        Ok(())
     } else {
-        Err(format!(
-            "Failed to fetch gitignore templates: {}",
-            res.status()
+        Err(CliError::git(
+            "Not a git repository.",
+            "Run 'git_mate init' first.",
         ))
     }
 }
 
-fn setup_gitignore(template: &str, config: &Config) {
-    println!(
+fn require_config() -> Result<Config, CliError> {
+    let config = load_config().map_err(CliError::config)?;
+    if config.active_profile() != config::DEFAULT_PROFILE {
+        say!("{} {}", "Using profile:".cyan(), config.active_profile());
+    }
+    Ok(config)
+}
+
+// SPDX identifiers such as MIT or GPL-3.0-or-later have capitals and are
+// used as they are; GitHub license keys are all lowercase and are mapped
+// with GitHub's license list, which also knows lowercased SPDX identifiers
+fn spdx_id(config: &Config, license: &str) -> Result<String, CliError> {
+    if license.chars().any(|c| c.is_ascii_uppercase()) {
+        return Ok(license.to_string());
+    }
+
+    let found = GitHubClient::new(config)
+        .and_then(|client| client.licenses())
+        .map_err(|e| CliError::github("Failed to fetch licenses:", e))?
+        .into_iter()
+        .find(|candidate| {
+            candidate.key == license
+                || candidate
+                    .spdx_id
+                    .as_deref()
+                    .is_some_and(|id| id.eq_ignore_ascii_case(license))
+        })
+        .ok_or_else(|| {
+            CliError::usage(format!(
+                "Unknown license '{}'. Use an SPDX identifier such as GPL-3.0-or-later or a GitHub license key such as mit.",
+                license
+            ))
+        })?;
+    found
+        .spdx_id
+        .filter(|id| id != "NOASSERTION")
+        .ok_or_else(|| CliError::usage(format!("License '{}' has no SPDX identifier.", license)))
+}
+
+fn get_gitignore_templates(config: &Config) -> Result<Vec<String>, GitHubError> {
+    GitHubClient::new(config)?.get("/gitignore/templates")
+}
+
+fn setup_gitignore(template: &str, config: &Config) -> Result<(), CliError> {
+    say!(
         "{} {}",
         "Setting up .gitignore with template:".cyan(),
         template
     );
 
-    let client = Client::new();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        ACCEPT,
-        HeaderValue::from_static("application/vnd.github.v3+json"),
-    );
-    headers.insert(USER_AGENT, HeaderValue::from_static("Git-Mate-CLI"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("token {}", config.github_token))
-            .unwrap_or_else(|_| HeaderValue::from_static("")),
-    );
+    let json: Value = GitHubClient::new(config)
+        .and_then(|client| client.get(&format!("/gitignore/templates/{}", template)))
+        .map_err(|e| CliError::github("Failed to fetch gitignore template:", e))?;
 
-    let url = format!("{}/{}", GITIGNORE_API_URL, template);
-
-    match client.get(&url).headers(headers).send() {
-        Ok(res) => {
-            if res.status().is_success() {
-                match res.json::<Value>() {
-                    Ok(json) => {
-                        if let Some(content) = json["source"].as_str() {
-                            // check if .gitignore already exists
-                            let gitignore_path = Path::new(".gitignore");
-                            let content = if gitignore_path.exists() {
-                                let existing =
-                                    fs::read_to_string(gitignore_path).unwrap_or_default();
-                                format!("{}\n\n# Added by Git-Mate\n{}", existing, content)
-                            } else {
-                                format!(
-                                    "# Created by Git-Mate using {} template\n{}",
-                                    template, content
-                                )
-                            };
-
-                            match fs::write(gitignore_path, content) {
-                                Ok(_) => {
-                                    println!("{}", ".gitignore file created successfully.".green())
-                                }
-                                Err(e) => println!("{} {}", "Failed to write .gitignore:".red(), e),
-                            }
-                        } else {
-                            println!("{}", "Invalid response format from GitHub API.".red());
-                        }
-                    }
-                    Err(e) => println!("{} {}", "Failed to parse GitHub API response:".red(), e),
-                }
-            } else {
-                println!(
-                    "{} {}",
-                    "Failed to fetch gitignore template:".red(),
-                    res.status()
-                );
-            }
-        }
-        Err(e) => println!("{} {}", "Failed to connect to GitHub API:".red(), e),
+    if let Some(content) = json["source"].as_str() {
+        // check if .gitignore already exists
+        let gitignore_path = Path::new(".gitignore");
+        let content = if gitignore_path.exists() {
+            let existing = fs::read_to_string(gitignore_path).unwrap_or_default();
+            format!("{}\n\n# Added by Git-Mate\n{}", existing, content)
+        } else {
+            format!(
+                "# Created by Git-Mate using {} template\n{}",
+                template, content
+            )
+        };
+
+        dry_run::write(gitignore_path, content)
+            .map_err(|e| CliError::general(format!("Failed to write .gitignore: {}", e)))?;
+        say!("{}", ".gitignore file created successfully.".green());
+        Ok(())
+    } else {
+        Err(CliError::general(
+            "Invalid response format from GitHub API.",
+        ))
     }
 }
 
-fn setup_license(license: &str, config: &Config) {
-    println!("{} {}", "Setting up license:".cyan(), license);
+fn setup_license(license: &str, holder: &str, config: &Config) -> Result<(), CliError> {
+    say!("{} {}", "Setting up license:".cyan(), license);
 
-    let client = Client::new();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        ACCEPT,
-        HeaderValue::from_static("application/vnd.github.v3+json"),
-    );
-    headers.insert(USER_AGENT, HeaderValue::from_static("Git-Mate-CLI"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("token {}", config.github_token))
-            .unwrap_or_else(|_| HeaderValue::from_static("")),
-    );
+    let json: Value = GitHubClient::new(config)
+        .and_then(|client| client.get(&format!("/licenses/{}", license)))
+        .map_err(|e| CliError::github("Failed to fetch license:", e))?;
 
-    let url = format!("https://api.github.com/licenses/{}", license);
-
-    match client.get(&url).headers(headers).send() {
-        Ok(res) => {
-            if res.status().is_success() {
-                match res.json::<Value>() {
-                    Ok(json) => {
-                        if let Some(content) = json["body"].as_str() {
-                            match fs::write("LICENSE", content) {
-                                Ok(_) => {
-                                    println!("{}", "LICENSE file created successfully.".green())
-                                }
-                                Err(e) => println!("{} {}", "Failed to write LICENSE:".red(), e),
-                            }
-                        } else {
-                            println!("{}", "Invalid response format from GitHub API.".red());
-                        }
-                    }
-                    Err(e) => println!("{} {}", "Failed to parse GitHub API response:".red(), e),
-                }
-            } else {
-                println!("{} {}", "Failed to fetch license:".red(), res.status());
-            }
-        }
-        Err(e) => println!("{} {}", "Failed to connect to GitHub API:".red(), e),
+    if let Some(content) = json["body"].as_str() {
+        let content = license::fill_placeholders(content, license::current_year(), holder);
+        dry_run::write("LICENSE", content)
+            .map_err(|e| CliError::general(format!("Failed to write LICENSE: {}", e)))?;
+        say!("{}", "LICENSE file created successfully.".green());
+        Ok(())
+    } else {
+        Err(CliError::general(
+            "Invalid response format from GitHub API.",
+        ))
     }
 }
 
@@ -1115,19 +3115,8 @@ fn create_github_issue(
     config: &Config,
     repo_name: &str,
     issue: &IssueInfo,
-) -> Result<String, String> {
-    let client = Client::new();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        ACCEPT,
-        HeaderValue::from_static("application/vnd.github.v3+json"),
-    );
-    headers.insert(USER_AGENT, HeaderValue::from_static("Git-Mate-CLI"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("token {}", config.github_token))
-            .map_err(|e| e.to_string())?,
-    );
+) -> Result<CreatedItem, GitHubError> {
+    let client = GitHubClient::new(config)?;
 
     let issue_data = json!({
         "title": issue.title,
@@ -1135,27 +3124,8 @@ fn create_github_issue(
         "labels": issue.labels
     });
 
-    let url = format!("https://api.github.com/repos/{}/issues", repo_name);
-
-    let res = client
-        .post(&url)
-        .headers(headers)
-        .json(&issue_data)
-        .send()
-        .map_err(|e| e.to_string())?;
-
-    if res.status().is_success() {
-        let json: Value = res.json().map_err(|e| e.to_string())?;
-        if let Some(html_url) = json["html_url"].as_str() {
-            Ok(html_url.to_string())
-        } else {
-            Err("Failed to get issue URL from GitHub response".to_string())
-        }
-    } else {
-        let status = res.status();
-        let text = res.text().unwrap_or_else(|_| "Unknown error".to_string());
-        Err(format!("GitHub API error ({}): {}", status, text))
-    }
+    let json: Value = client.post(&format!("/repos/{}/issues", repo_name), &issue_data)?;
+    created_item(&json, "issue")
 }
 
 fn create_github_pr(
@@ -1165,19 +3135,8 @@ fn create_github_pr(
     body: &str,
     base: &str,
     head: &str,
-) -> Result<String, String> {
-    let client = Client::new();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        ACCEPT,
-        HeaderValue::from_static("application/vnd.github.v3+json"),
-    );
-    headers.insert(USER_AGENT, HeaderValue::from_static("Git-Mate-CLI"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("token {}", config.github_token))
-            .map_err(|e| e.to_string())?,
-    );
+) -> Result<CreatedItem, GitHubError> {
+    let client = GitHubClient::new(config)?;
 
     let pr_data = json!({
         "title": title,
@@ -1186,122 +3145,152 @@ fn create_github_pr(
         "base": base
     });
 
-    let url = format!("https://api.github.com/repos/{}/pulls", repo_name);
+    let json: Value = client.post(&format!("/repos/{}/pulls", repo_name), &pr_data)?;
+    created_item(&json, "pull request")
+}
 
-    let res = client
-        .post(&url)
-        .headers(headers)
-        .json(&pr_data)
-        .send()
-        .map_err(|e| e.to_string())?;
+fn add_labels(
+    config: &Config,
+    repo_name: &str,
+    number: u64,
+    labels: &[String],
+) -> Result<(), GitHubError> {
+    let client = GitHubClient::new(config)?;
+    let _: Value = client.post(
+        &format!("/repos/{}/issues/{}/labels", repo_name, number),
+        &json!({ "labels": labels }),
+    )?;
+    Ok(())
+}
 
-    if res.status().is_success() {
-        let json: Value = res.json().map_err(|e| e.to_string())?;
-        if let Some(html_url) = json["html_url"].as_str() {
-            Ok(html_url.to_string())
-        } else {
-            Err("Failed to get PR URL from GitHub response".to_string())
-        }
-    } else {
-        let status = res.status();
-        let text = res.text().unwrap_or_else(|_| "Unknown error".to_string());
-        Err(format!("GitHub API error ({}): {}", status, text))
+fn created_item(json: &Value, kind: &str) -> Result<CreatedItem, GitHubError> {
+    if dry_run::is_dry_run() {
+        return Ok(CreatedItem {
+            number: 0,
+            html_url: format!("(no {} created in a dry run)", kind),
+        });
+    }
+    match (json["number"].as_u64(), json["html_url"].as_str()) {
+        (Some(number), Some(html_url)) => Ok(CreatedItem {
+            number,
+            html_url: html_url.to_string(),
+        }),
+        _ => Err(GitHubError::Decode(format!(
+            "missing {} number or URL",
+            kind
+        ))),
     }
 }
 
-fn setup_workflow(workflow_type: &str) {
-    // create .github/workflows directory if it doesn't exist
-    let workflows_dir = Path::new(".github").join("workflows");
-    fs::create_dir_all(&workflows_dir).unwrap_or_else(|e| {
-        println!("{} {}", "Failed to create workflows directory:".red(), e);
-        return;
-    });
+// the repository name on GitHub, or the directory name before there is one
+fn project_name() -> String {
+    get_origin().map(|origin| origin.repo).unwrap_or_else(|_| {
+        Path::new(".")
+            .canonicalize()
+            .ok()
+            .and_then(|dir| {
+                dir.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+            .unwrap_or_default()
+    })
+}
 
-    let (filename, content) = match workflow_type {
-        "ci" => (
-            "ci.yml",
-            r#"name: CI
-        
-            on:
-            push:
-            branches: [ main, master ]
-            pull_request:
-            branches: [ main, master ]
-            
-            jobs:
-            build:
-            runs-on: ubuntu-latest
-            steps:
-            - uses: actions/checkout@v3
-    
-            - name: Set up environment
-            run: echo "Setting up environment"
-      
-            - name: Build
-            run: echo "Building project"
-      
-            - name: Run tests
-            run: echo "Running tests"
-            "#,
-        ),
-        "deploy" => (
-            "deploy.yml",
-            r#"name: Deploy
-
-            on:
-            push:
-            branches: [ main, master ]
-
-            jobs:
-            deploy:
-            runs-on: ubuntu-latest
-    
-            steps:
-            - uses: actions/checkout@v3
-    
-            - name: Set up environment
-            run: echo "Setting up environment"
-      
-            - name: Build
-            run: echo "Building project"
-      
-            - name: Deploy to production
-            run: echo "Deploying to production"
-            "#,
-        ),
-        _ => (
-            "custom.yml",
-            r#"name: Custom Workflow
-
-            on:
-            push:
-            branches: [ main, master ]
-            pull_request:
-            branches: [ main, master ]
-
-            jobs:
-            custom:
-            runs-on: ubuntu-latest
-    
-            steps:
-            - uses: actions/checkout@v3
-    
-            - name: Custom step 1
-            run: echo "Running custom step 1"
-      
-            - name: Custom step 2
-            run: echo "Running custom step 2"
-            "#,
-        ),
+// the path and contents of a workflow, checked to be valid
+fn prepare_workflow(workflow_type: &str, config: &Config) -> Result<(PathBuf, String), CliError> {
+    let project = load_project_config().map_err(CliError::config)?;
+    let templates = workflow::templates(&project).map_err(CliError::config)?;
+
+    // build for the project's language, on pushes to the default branch
+    let detected = detect_language(Path::new("."));
+    let default_branch = config.default_branch.as_deref().unwrap_or("main");
+    let (filename, content) = match templates.iter().find(|t| t.name == workflow_type) {
+        Some(template) => {
+            say!("{} {}", "Using workflow template".cyan(), template.name);
+            let variables = workflow::Variables::new(
+                &project_name(),
+                default_branch,
+                detected.as_ref(),
+                Path::new("."),
+            );
+            let content = template
+                .contents()
+                .and_then(|contents| workflow::render(&contents, &variables))
+                .map_err(|e| {
+                    CliError::config(format!("Workflow template '{}': {}", template.name, e))
+                })?;
+            (template.file_name.clone(), content)
+        }
+        None if workflow::BUILTINS
+            .iter()
+            .any(|(name, _)| *name == workflow_type) =>
+        {
+            if let Some((language, _)) = &detected {
+                say!("{} {}", "Using workflow steps for".cyan(), language.name);
+            }
+            let (filename, content) =
+                workflow::builtin(workflow_type, detected, default_branch, Path::new("."));
+            (filename.to_string(), content)
+        }
+        None => {
+            let available: Vec<&str> = workflow::BUILTINS
+                .iter()
+                .map(|(name, _)| *name)
+                .chain(templates.iter().map(|t| t.name.as_str()))
+                .collect();
+            return Err(CliError::usage(format!(
+                "Unknown workflow '{}'. Available: {}",
+                workflow_type,
+                available.join(", ")
+            )));
+        }
     };
 
-    let file_path = workflows_dir.join(filename);
-    match fs::write(&file_path, content) {
-        Ok(_) => println!(
-            "{} {}",
-            "GitHub Actions workflow created:".green(),
+    let errors = workflow::validate(&content);
+    if !errors.is_empty() {
+        return Err(CliError::new(
+            ErrorKind::Validation,
+            format!(
+                "The '{}' workflow is not valid:\n  {}",
+                workflow_type,
+                errors.join("\n  ")
+            ),
+        ));
+    }
+    Ok((
+        Path::new(".github").join("workflows").join(filename),
+        content,
+    ))
+}
+
+// write a workflow; an existing file is only replaced with `force`
+fn setup_workflow(workflow_type: &str, config: &Config, force: bool) -> Result<String, CliError> {
+    let (file_path, content) = prepare_workflow(workflow_type, config)?;
+    if file_path.exists() && !force {
+        return Err(CliError::usage(format!(
+            "{} already exists. Use --force to overwrite it.",
             file_path.display()
-        ),
-        Err(e) => println!("{} {}", "Failed to create workflow file:".red(), e),
+        )));
     }
+    write_workflow(&file_path, &content)
+}
+
+fn write_workflow(file_path: &Path, content: &str) -> Result<String, CliError> {
+    // create .github/workflows directory if it doesn't exist
+    if let Some(workflows_dir) = file_path.parent() {
+        if !dry_run::is_dry_run() {
+            fs::create_dir_all(workflows_dir).map_err(|e| {
+                CliError::general(format!("Failed to create workflows directory: {}", e))
+            })?;
+        }
+    }
+
+    dry_run::write(file_path, content)
+        .map_err(|e| CliError::general(format!("Failed to create workflow file: {}", e)))?;
+    say!(
+        "{} {}",
+        "GitHub Actions workflow created:".green(),
+        file_path.display()
+    );
+    Ok(file_path.display().to_string())
 }
@@ -0,0 +1,192 @@
+use crate::dry_run;
+use crate::github::GitHubError;
+use colored::*;
+use indicatif::ProgressBar;
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Print a human-readable status line. In `--json` mode these go to stderr
+/// so stdout only carries the final JSON document.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+pub fn set_json(enabled: bool) {
+    JSON_OUTPUT.store(enabled, Ordering::Relaxed);
+    if enabled {
+        colored::control::set_override(false);
+    }
+}
+
+pub fn is_json() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// A spinner for long waits; hidden in `--json` mode.
+pub fn spinner(message: &str) -> ProgressBar {
+    if is_json() {
+        return ProgressBar::hidden();
+    }
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_message(message.to_string());
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner
+}
+
+/// Broad failure categories, each mapped to a documented process exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    General,
+    Usage,
+    Config,
+    Git,
+    Auth,
+    NotFound,
+    Validation,
+    Network,
+    Cancelled,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::General => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Config => 3,
+            ErrorKind::Git => 4,
+            ErrorKind::Auth => 5,
+            ErrorKind::NotFound => 6,
+            ErrorKind::Validation => 7,
+            ErrorKind::Network => 8,
+            ErrorKind::Cancelled => 9,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::General => "general",
+            ErrorKind::Usage => "usage",
+            ErrorKind::Config => "config",
+            ErrorKind::Git => "git",
+            ErrorKind::Auth => "auth",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::Validation => "validation",
+            ErrorKind::Network => "network",
+            ErrorKind::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Error returned by a subcommand.
+#[derive(Debug)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        CliError {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn general(message: impl Into<String>) -> Self {
+        CliError::new(ErrorKind::General, message)
+    }
+
+    pub fn usage(message: impl Into<String>) -> Self {
+        CliError::new(ErrorKind::Usage, message)
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        CliError::new(ErrorKind::Config, message)
+    }
+
+    pub fn git(context: &str, error: impl fmt::Display) -> Self {
+        CliError::new(ErrorKind::Git, format!("{} {}", context, error))
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        CliError::new(ErrorKind::Cancelled, message)
+    }
+
+    pub fn github(context: &str, error: GitHubError) -> Self {
+        let kind = match error {
+            GitHubError::Auth { .. } => ErrorKind::Auth,
+            GitHubError::NotFound { .. } => ErrorKind::NotFound,
+            GitHubError::Validation { .. } => ErrorKind::Validation,
+            GitHubError::Api { .. } | GitHubError::Network(_) | GitHubError::Decode(_) => {
+                ErrorKind::Network
+            }
+        };
+        CliError::new(kind, format!("{} {}", context, error))
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Structured result of a successful subcommand, emitted with `--json`.
+pub type CommandResult = Result<Value, CliError>;
+
+/// Report the outcome of `command` and return the process exit code.
+pub fn finish(command: &str, result: CommandResult) -> i32 {
+    match result {
+        Ok(data) => {
+            if is_json() {
+                let mut result = json!({
+                    "ok": true,
+                    "command": command,
+                    "data": data,
+                });
+                if dry_run::is_dry_run() {
+                    result["dry_run"] = json!(true);
+                    result["planned"] = json!(dry_run::planned());
+                }
+                print_json(&result);
+            } else if dry_run::is_dry_run() {
+                say!("{}", "Dry run: nothing was changed.".magenta().bold());
+            }
+            0
+        }
+        Err(e) => {
+            let code = e.kind.exit_code();
+            if is_json() {
+                print_json(&json!({
+                    "ok": false,
+                    "command": command,
+                    "error": {
+                        "kind": e.kind.as_str(),
+                        "message": e.message,
+                        "exit_code": code,
+                    },
+                }));
+            } else {
+                eprintln!("{}", e.message.red());
+            }
+            code
+        }
+    }
+}
+
+fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+    );
+}
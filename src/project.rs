@@ -0,0 +1,206 @@
+use crate::git::run_command;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::str;
+
+pub const PROJECT_CONFIG_FILE: &str = ".git_mate.toml";
+
+const CONVENTIONAL_TYPES: &[&str] = &[
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+
+/// Shared per-repository defaults, read from `.git_mate.toml` at the
+/// repository root. Credentials and the API URL are deliberately not
+/// accepted here since the file is committed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Account profile to use for this repository.
+    pub profile: Option<String>,
+    pub default_branch: Option<String>,
+    pub default_license: Option<String>,
+    pub commit: CommitConfig,
+    pub issue: IssueConfig,
+    pub pr: PrConfig,
+    pub workflow: WorkflowConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommitConfig {
+    /// Default commit message offered by `push`.
+    pub default_message: Option<String>,
+    /// Commit message convention to enforce; only "conventional" is known.
+    pub convention: Option<String>,
+    /// Allowed types for conventional commits (defaults to the usual set).
+    pub types: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IssueConfig {
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrConfig {
+    pub base: Option<String>,
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkflowConfig {
+    /// Workflow used when none is given: ci, deploy, custom or a template name.
+    #[serde(rename = "type")]
+    pub workflow_type: Option<String>,
+    /// Workflow templates offered by `git_mate workflow`, by name.
+    pub templates: BTreeMap<String, WorkflowTemplateConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowTemplateConfig {
+    /// Template file, relative to the repository root.
+    pub path: String,
+    pub description: Option<String>,
+}
+
+impl CommitConfig {
+    /// Check `message` against the configured convention.
+    pub fn check_message(&self, message: &str) -> Result<(), String> {
+        match self.convention.as_deref() {
+            None => Ok(()),
+            Some("conventional") => self.check_conventional(message),
+            Some(other) => Err(format!(
+                "Unknown commit convention '{}' in {}",
+                other, PROJECT_CONFIG_FILE
+            )),
+        }
+    }
+
+    // <type>[(scope)][!]: <description>
+    fn check_conventional(&self, message: &str) -> Result<(), String> {
+        let types: Vec<&str> = if self.types.is_empty() {
+            CONVENTIONAL_TYPES.to_vec()
+        } else {
+            self.types.iter().map(|t| t.as_str()).collect()
+        };
+        let invalid = || {
+            format!(
+                "Commit message does not follow the conventional commit format '<type>(<scope>): <description>' required by {}. Allowed types: {}",
+                PROJECT_CONFIG_FILE,
+                types.join(", ")
+            )
+        };
+
+        let subject = message.lines().next().unwrap_or("");
+        let (header, description) = subject.split_once(": ").ok_or_else(invalid)?;
+        let header = header.strip_suffix('!').unwrap_or(header);
+        let commit_type = match header.split_once('(') {
+            Some((commit_type, scope)) => {
+                let scope = scope.strip_suffix(')').ok_or_else(invalid)?;
+                if scope.is_empty() || scope.contains(['(', ')']) {
+                    return Err(invalid());
+                }
+                commit_type
+            }
+            None => header,
+        };
+
+        if types.contains(&commit_type) && !description.trim().is_empty() {
+            Ok(())
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+/// The repository root, or the current directory outside a repository.
+pub fn project_root() -> PathBuf {
+    run_command("git", &["rev-parse", "--show-toplevel"])
+        .ok()
+        .and_then(|output| {
+            str::from_utf8(&output.stdout)
+                .ok()
+                .map(|root| PathBuf::from(root.trim()))
+        })
+        .filter(|root| !root.as_os_str().is_empty())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Load `.git_mate.toml` from the repository root; missing is not an error.
+pub fn load_project_config() -> Result<ProjectConfig, String> {
+    let path = project_root().join(PROJECT_CONFIG_FILE);
+    if !path.exists() {
+        return Ok(ProjectConfig::default());
+    }
+
+    let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    toml::from_str(&contents).map_err(|e| format!("Invalid {}: {}", PROJECT_CONFIG_FILE, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conventional(types: &[&str]) -> CommitConfig {
+        CommitConfig {
+            convention: Some("conventional".to_string()),
+            types: types.iter().map(|t| t.to_string()).collect(),
+            ..CommitConfig::default()
+        }
+    }
+
+    #[test]
+    fn accepts_conventional_messages() {
+        let config = conventional(&[]);
+        for message in [
+            "feat: x",
+            "fix(scope)!: x",
+            "feat!: drop the old flag",
+            "docs(readme): x\n\nA longer body.",
+        ] {
+            assert!(config.check_message(message).is_ok(), "{}", message);
+        }
+    }
+
+    #[test]
+    fn rejects_other_messages() {
+        let config = conventional(&[]);
+        for message in [
+            "feat(): x",
+            "feat(a(b)): x",
+            "feat(a: x",
+            "feat:x",
+            "feat: ",
+            "feature: x",
+            "Fix the build",
+            "",
+        ] {
+            assert!(config.check_message(message).is_err(), "{:?}", message);
+        }
+    }
+
+    #[test]
+    fn custom_types_replace_the_defaults() {
+        let config = conventional(&["hotfix", "feat"]);
+        assert!(config.check_message("hotfix(api): x").is_ok());
+        assert!(config.check_message("feat: x").is_ok());
+        let error = config.check_message("fix: x").unwrap_err();
+        assert!(error.ends_with("Allowed types: hotfix, feat"), "{}", error);
+    }
+
+    #[test]
+    fn no_convention_accepts_anything() {
+        assert!(CommitConfig::default().check_message("wip").is_ok());
+        let unknown = CommitConfig {
+            convention: Some("gitmoji".to_string()),
+            ..CommitConfig::default()
+        };
+        assert!(unknown.check_message("feat: x").is_err());
+    }
+}
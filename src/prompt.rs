@@ -0,0 +1,125 @@
+use crate::output::CliError;
+use clap::ArgMatches;
+use dialoguer::{Confirm, Input, Password, Select};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static NO_INPUT: AtomicBool = AtomicBool::new(false);
+
+/// Disable prompts. Also enabled automatically when stdin is not a terminal.
+pub fn set_no_input(enabled: bool) {
+    NO_INPUT.store(
+        enabled || !std::io::stdin().is_terminal(),
+        Ordering::Relaxed,
+    );
+}
+
+pub fn is_interactive() -> bool {
+    !NO_INPUT.load(Ordering::Relaxed)
+}
+
+/// In non-interactive mode, fail with a single error listing every required
+/// argument that was not given on the command line. `args` pairs the clap
+/// argument name with how it is spelled for the user.
+pub fn require_args(matches: &ArgMatches, args: &[(&str, &str)]) -> Result<(), CliError> {
+    if is_interactive() {
+        return Ok(());
+    }
+
+    let missing: Vec<&str> = args
+        .iter()
+        .filter(|(name, _)| !matches.is_present(name))
+        .map(|(_, flag)| *flag)
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(missing_args(&missing))
+    }
+}
+
+pub fn missing_args(flags: &[&str]) -> CliError {
+    CliError::usage(format!(
+        "Missing required arguments in non-interactive mode: {}",
+        flags.join(", ")
+    ))
+}
+
+/// Ask for a line of text, or return `default` without prompting when
+/// running non-interactively.
+pub fn input(prompt: &str, default: Option<String>) -> Result<String, CliError> {
+    if !is_interactive() {
+        return default.ok_or_else(|| {
+            CliError::usage(format!("'{}' is required in non-interactive mode", prompt))
+        });
+    }
+
+    let mut input = Input::<String>::new();
+    input.with_prompt(prompt);
+    match default {
+        Some(default) => {
+            input.default(default);
+        }
+        None => {
+            input.allow_empty(false);
+        }
+    }
+    input
+        .interact()
+        .map_err(|_| CliError::cancelled(format!("'{}' prompt cancelled.", prompt)))
+}
+
+/// Like `input`, but an empty answer is accepted.
+pub fn input_optional(prompt: &str) -> Result<String, CliError> {
+    if !is_interactive() {
+        return Ok(String::new());
+    }
+
+    Input::<String>::new()
+        .with_prompt(prompt)
+        .allow_empty(true)
+        .interact()
+        .map_err(|_| CliError::cancelled(format!("'{}' prompt cancelled.", prompt)))
+}
+
+/// Pick one of `items`; non-interactive runs get `default`.
+pub fn select<T: ToString>(prompt: &str, items: &[T], default: usize) -> Result<usize, CliError> {
+    if !is_interactive() {
+        return Ok(default);
+    }
+
+    Select::new()
+        .with_prompt(prompt)
+        .items(items)
+        .default(default)
+        .interact()
+        .map_err(|_| CliError::cancelled(format!("'{}' selection cancelled.", prompt)))
+}
+
+/// Yes/no question; non-interactive runs get `default`.
+pub fn confirm(prompt: &str, default: bool) -> Result<bool, CliError> {
+    if !is_interactive() {
+        return Ok(default);
+    }
+
+    Confirm::new()
+        .with_prompt(prompt)
+        .default(default)
+        .interact()
+        .map_err(|_| CliError::cancelled(format!("'{}' prompt cancelled.", prompt)))
+}
+
+/// Read a secret with confirmation. There is no sensible default for a
+/// secret, so this always fails in non-interactive mode.
+pub fn password(prompt: &str, flag: &str) -> Result<String, CliError> {
+    if !is_interactive() {
+        return Err(missing_args(&[flag]));
+    }
+
+    Password::new()
+        .with_prompt(prompt)
+        .with_confirmation("Confirm token", "Tokens don't match")
+        .interact()
+        .map_err(|_| CliError::cancelled(format!("'{}' prompt cancelled.", prompt)))
+}
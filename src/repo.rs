@@ -0,0 +1,164 @@
+use crate::output::CliError;
+use clap::{Arg, ArgMatches};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+const MERGE_METHODS: &[(&str, &str)] = &[
+    ("merge", "allow_merge_commit"),
+    ("squash", "allow_squash_merge"),
+    ("rebase", "allow_rebase_merge"),
+];
+
+/// The repository `init` creates on GitHub.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoInfo {
+    pub name: String,
+    /// Organization to create the repository under; `None` for the user's own account.
+    pub owner: Option<String>,
+    /// Login of the token's account, looked up when no username is configured.
+    #[serde(default)]
+    pub account: Option<String>,
+    pub description: String,
+    /// "public", "private" or "internal" (organizations only).
+    pub visibility: String,
+    /// Template repository (owner/name) to generate the repository from.
+    pub template: Option<String>,
+    #[serde(default)]
+    pub settings: RepoSettings,
+}
+
+/// A repository on GitHub that `init` created or links to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedRepo {
+    pub full_name: String,
+    /// The URL `origin` points at, one of the two below.
+    pub remote_url: String,
+    pub ssh_url: Option<String>,
+    pub clone_url: Option<String>,
+    pub html_url: String,
+    pub default_branch: Option<String>,
+}
+
+/// Repository settings shared by `init` and `repo edit`. Fields left as
+/// `None` are not sent, so GitHub keeps its default or current value.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RepoSettings {
+    pub homepage: Option<String>,
+    pub topics: Option<Vec<String>>,
+    pub has_issues: Option<bool>,
+    pub has_wiki: Option<bool>,
+    pub has_projects: Option<bool>,
+    /// Allowed merge methods out of "merge", "squash" and "rebase".
+    pub merge_methods: Option<Vec<String>>,
+    pub delete_branch_on_merge: Option<bool>,
+}
+
+/// Command-line options for [`RepoSettings`].
+pub fn settings_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    let toggle = |name: &'a str, help: &'b str| {
+        Arg::with_name(name)
+            .long(name)
+            .help(help)
+            .takes_value(true)
+            .value_name("on|off")
+            .possible_values(&["on", "off"])
+    };
+
+    vec![
+        Arg::with_name("homepage")
+            .long("homepage")
+            .help("Homepage URL shown on the repository page")
+            .takes_value(true),
+        Arg::with_name("topics")
+            .long("topics")
+            .help("Comma-separated repository topics")
+            .takes_value(true)
+            .use_delimiter(true)
+            .multiple(true),
+        toggle("issues", "Enable or disable issues"),
+        toggle("wiki", "Enable or disable the wiki"),
+        toggle("projects", "Enable or disable projects"),
+        Arg::with_name("merge-methods")
+            .long("merge-methods")
+            .help("Comma-separated allowed merge methods: merge, squash, rebase")
+            .takes_value(true)
+            .use_delimiter(true)
+            .multiple(true)
+            .possible_values(&["merge", "squash", "rebase"]),
+        toggle(
+            "delete-branch-on-merge",
+            "Delete head branches after pull requests are merged",
+        ),
+    ]
+}
+
+impl RepoSettings {
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, CliError> {
+        let toggle = |name: &str| matches.value_of(name).map(|value| value == "on");
+        let list = |name: &str| {
+            matches.values_of(name).map(|values| {
+                values
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        let merge_methods = list("merge-methods");
+        if merge_methods
+            .as_ref()
+            .is_some_and(|methods| methods.is_empty())
+        {
+            return Err(CliError::usage("At least one merge method must be allowed"));
+        }
+
+        Ok(RepoSettings {
+            homepage: matches
+                .value_of("homepage")
+                .map(|homepage| homepage.trim().to_string()),
+            topics: list("topics")
+                .map(|topics| topics.iter().map(|topic| topic.to_lowercase()).collect()),
+            has_issues: toggle("issues"),
+            has_wiki: toggle("wiki"),
+            has_projects: toggle("projects"),
+            merge_methods,
+            delete_branch_on_merge: toggle("delete-branch-on-merge"),
+        })
+    }
+
+    /// Fields for the create and update repository endpoints. Topics are
+    /// not part of these and go through [`RepoSettings::topics_body`].
+    pub fn fields(&self) -> Map<String, Value> {
+        let mut fields = Map::new();
+        if let Some(homepage) = &self.homepage {
+            fields.insert("homepage".to_string(), json!(homepage));
+        }
+        let flags = [
+            ("has_issues", self.has_issues),
+            ("has_wiki", self.has_wiki),
+            ("has_projects", self.has_projects),
+            ("delete_branch_on_merge", self.delete_branch_on_merge),
+        ];
+        for (name, value) in flags {
+            if let Some(value) = value {
+                fields.insert(name.to_string(), json!(value));
+            }
+        }
+        if let Some(methods) = &self.merge_methods {
+            for (method, field) in MERGE_METHODS {
+                fields.insert(
+                    field.to_string(),
+                    json!(methods.iter().any(|m| m == method)),
+                );
+            }
+        }
+        fields
+    }
+
+    /// Body for `PUT /repos/{owner}/{repo}/topics`, if topics were given.
+    pub fn topics_body(&self) -> Option<Value> {
+        self.topics
+            .as_ref()
+            .map(|topics| json!({ "names": topics }))
+    }
+}
@@ -0,0 +1,862 @@
+use crate::config::workflow_templates_dir;
+use crate::detect::Language;
+use crate::project::{project_root, ProjectConfig, PROJECT_CONFIG_FILE};
+use serde_norway::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const RUNNERS: &str = "[ubuntu-latest, macos-latest, windows-latest]";
+
+/// Built-in workflows and how they are shown in the selector.
+pub const BUILTINS: &[(&str, &str)] = &[
+    ("ci", "Continuous Integration"),
+    ("deploy", "Deployment"),
+    ("custom", "Custom"),
+];
+
+/// A workflow template from `~/.git_mate/workflows` or `.git_mate.toml`.
+#[derive(Debug)]
+pub struct Template {
+    pub name: String,
+    pub description: String,
+    /// File name written under `.github/workflows`.
+    pub file_name: String,
+    path: PathBuf,
+}
+
+/// Values for the `{{ name }}` placeholders in workflow templates.
+#[derive(Debug)]
+pub struct Variables {
+    pub project: String,
+    pub default_branch: String,
+    pub language: Option<String>,
+    /// Newest toolchain version the built-in workflows test with.
+    pub language_version: Option<String>,
+}
+
+/// How to set up, build and test a project of one language in GitHub Actions.
+struct Toolchain {
+    name: &'static str,
+    /// Matrix variable holding the toolchain version.
+    matrix_key: &'static str,
+    /// Versions tested in CI; the last one is used for deployments.
+    versions: &'static [&'static str],
+    /// Setup steps, with `{version}` for the toolchain version.
+    setup: String,
+    build: String,
+    test: String,
+}
+
+// the toolchain for a detected language, adapted to the files the project
+// has (lock files, build tool wrappers). `None` for languages without one.
+fn toolchain(language: &Language, marker: &str, dir: &Path) -> Option<Toolchain> {
+    let has = |file: &str| dir.join(file).exists();
+
+    let toolchain = match language.name {
+        "Rust" => Toolchain {
+            name: "Rust",
+            matrix_key: "rust",
+            versions: &["beta", "stable"],
+            setup: r#"      - uses: dtolnay/rust-toolchain@master
+        with:
+          toolchain: {version}
+          components: clippy
+      - uses: Swatinem/rust-cache@v2
+"#
+            .to_string(),
+            build: r#"      - name: Build
+        run: cargo build --workspace --all-targets
+      - name: Clippy
+        run: cargo clippy --workspace --all-targets -- -D warnings
+"#
+            .to_string(),
+            test: r#"      - name: Test
+        run: cargo test --workspace
+"#
+            .to_string(),
+        },
+        "Node.js" => {
+            // npm caching and `npm ci` both need the lock file
+            let (cache, install) = if has("package-lock.json") {
+                ("\n          cache: npm", "npm ci")
+            } else {
+                ("", "npm install")
+            };
+            Toolchain {
+                name: "Node.js",
+                matrix_key: "node",
+                versions: &["lts/-1", "lts/*"],
+                setup: format!(
+                    r#"      - uses: actions/setup-node@v4
+        with:
+          node-version: {{version}}{}
+"#,
+                    cache
+                ),
+                build: format!(
+                    r#"      - name: Install dependencies
+        run: {}
+      - name: Build
+        run: npm run build --if-present
+"#,
+                    install
+                ),
+                test: r#"      - name: Test
+        run: npm test
+"#
+                .to_string(),
+            }
+        }
+        "Python" => {
+            // pip caching is keyed on a dependency file, which must exist
+            let dependencies = if has("requirements.txt") {
+                "requirements.txt"
+            } else {
+                marker
+            };
+            Toolchain {
+                name: "Python",
+                matrix_key: "python",
+                versions: &["3.11", "3.12", "3.13"],
+                setup: format!(
+                    r#"      - uses: actions/setup-python@v5
+        with:
+          python-version: "{{version}}"
+          cache: pip
+          cache-dependency-path: {}
+"#,
+                    dependencies
+                ),
+                build: r#"      - name: Install dependencies
+        shell: bash
+        run: |
+          python -m pip install --upgrade pip
+          if [ -f requirements.txt ]; then pip install -r requirements.txt; fi
+          if [ -f pyproject.toml ] || [ -f setup.py ]; then pip install -e .; fi
+          pip install pytest
+"#
+                .to_string(),
+                test: r#"      - name: Test
+        run: pytest
+"#
+                .to_string(),
+            }
+        }
+        "Go" => Toolchain {
+            name: "Go",
+            matrix_key: "go",
+            versions: &["oldstable", "stable"],
+            setup: r#"      - uses: actions/setup-go@v5
+        with:
+          go-version: {version}
+"#
+            .to_string(),
+            build: r#"      - name: Build
+        run: go build ./...
+      - name: Vet
+        run: go vet ./...
+"#
+            .to_string(),
+            test: r#"      - name: Test
+        run: go test ./...
+"#
+            .to_string(),
+        },
+        "Java" => {
+            let maven = marker == "pom.xml";
+            let (build, test) = if maven {
+                ("mvn -B -DskipTests package", "mvn -B verify")
+            } else if has("gradlew") {
+                ("./gradlew assemble", "./gradlew check")
+            } else {
+                ("gradle assemble", "gradle check")
+            };
+            // without a wrapper, Gradle itself has to be installed
+            let gradle_setup = if !maven && !has("gradlew") {
+                "      - uses: gradle/actions/setup-gradle@v4\n"
+            } else {
+                ""
+            };
+            Toolchain {
+                name: "Java",
+                matrix_key: "java",
+                versions: &["17", "21"],
+                setup: format!(
+                    r#"      - uses: actions/setup-java@v4
+        with:
+          distribution: temurin
+          java-version: "{{version}}"
+          cache: {}
+{}"#,
+                    if maven { "maven" } else { "gradle" },
+                    gradle_setup
+                ),
+                build: format!(
+                    r#"      - name: Build
+        shell: bash
+        run: {}
+"#,
+                    build
+                ),
+                test: format!(
+                    r#"      - name: Test
+        shell: bash
+        run: {}
+"#,
+                    test
+                ),
+            }
+        }
+        _ => return None,
+    };
+    Some(toolchain)
+}
+
+/// Templates from `~/.git_mate/workflows/*.yml` and the project's
+/// `.git_mate.toml`. A project template replaces a user template of the
+/// same name; either replaces a built-in workflow of that name.
+pub fn templates(project: &ProjectConfig) -> Result<Vec<Template>, String> {
+    let mut templates = Vec::new();
+
+    if let Some(dir) = workflow_templates_dir().filter(|dir| dir.is_dir()) {
+        let entries =
+            fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "yml" || extension == "yaml")
+            })
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let (Some(name), Some(file_name)) = (path.file_stem(), path.file_name()) {
+                templates.push(Template {
+                    name: name.to_string_lossy().to_string(),
+                    description: format!("from {}", dir.display()),
+                    file_name: file_name.to_string_lossy().to_string(),
+                    path: path.clone(),
+                });
+            }
+        }
+    }
+
+    let root = project_root();
+    for (name, template) in &project.workflow.templates {
+        templates.retain(|existing| existing.name != *name);
+        templates.push(Template {
+            name: name.clone(),
+            description: template
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("from {}", PROJECT_CONFIG_FILE)),
+            file_name: format!("{}.yml", name),
+            path: root.join(&template.path),
+        });
+    }
+    Ok(templates)
+}
+
+impl Template {
+    pub fn contents(&self) -> Result<String, String> {
+        fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read template {}: {}", self.path.display(), e))
+    }
+}
+
+impl Variables {
+    pub fn new(
+        project: &str,
+        default_branch: &str,
+        detected: Option<&(&Language, String)>,
+        dir: &Path,
+    ) -> Self {
+        let language_version = detected
+            .and_then(|(language, marker)| toolchain(language, marker, dir))
+            .and_then(|toolchain| toolchain.versions.last().map(|v| v.to_string()));
+        Variables {
+            project: project.to_string(),
+            default_branch: default_branch.to_string(),
+            language: detected.map(|(language, _)| language.name.to_string()),
+            language_version,
+        }
+    }
+
+    fn get(&self, name: &str) -> Result<&str, String> {
+        let value = match name {
+            "project" => Some(&self.project),
+            "default_branch" => Some(&self.default_branch),
+            "language" => self.language.as_ref(),
+            "language_version" => self.language_version.as_ref(),
+            _ => {
+                return Err(format!(
+                    "Unknown variable '{{{{ {} }}}}'. Available: project, default_branch, language, language_version",
+                    name
+                ))
+            }
+        };
+        // only the language variables can be missing
+        value.map(|value| value.as_str()).ok_or_else(|| {
+            format!(
+                "'{{{{ {} }}}}' needs the project's language, which wasn't detected",
+                name
+            )
+        })
+    }
+}
+
+/// Replace `{{ name }}` placeholders in `template`. GitHub expressions,
+/// which look the same but start with `$`, are left alone.
+pub fn render(template: &str, variables: &Variables) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .map(|end| start + end + 2)
+            .ok_or_else(|| "Unclosed '{{' in template".to_string())?;
+        rendered.push_str(&rest[..start]);
+        if rendered.ends_with('$') {
+            rendered.push_str(&rest[start..end]);
+        } else {
+            rendered.push_str(variables.get(rest[start + 2..end - 2].trim())?);
+        }
+        rest = &rest[end..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// The file name and contents of a built-in workflow: "ci", "deploy" or
+/// "custom". `detected` is the project's language and marker file, if known.
+pub fn builtin(
+    workflow_type: &str,
+    detected: Option<(&Language, String)>,
+    default_branch: &str,
+    dir: &Path,
+) -> (&'static str, String) {
+    let toolchain = detected.and_then(|(language, marker)| toolchain(language, &marker, dir));
+
+    match workflow_type {
+        "ci" => ("ci.yml", ci(toolchain.as_ref(), default_branch)),
+        "deploy" => ("deploy.yml", deploy(toolchain.as_ref(), default_branch)),
+        _ => ("custom.yml", custom(toolchain.as_ref(), default_branch)),
+    }
+}
+
+// build and test on every runner OS and toolchain version
+fn ci(toolchain: Option<&Toolchain>, branch: &str) -> String {
+    let header = format!(
+        r#"name: CI
+
+on:
+  push:
+    branches: [ {branch} ]
+  pull_request:
+    branches: [ {branch} ]
+
+"#
+    );
+
+    let toolchain = match toolchain {
+        Some(toolchain) => toolchain,
+        None => {
+            return header
+                + r#"jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      # no build steps are known for this project's language; add them here
+      - name: Build
+        run: echo "Add the commands that build and test this project"
+"#
+        }
+    };
+
+    let versions = toolchain
+        .versions
+        .iter()
+        .map(|version| format!("\"{}\"", version))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let key = toolchain.matrix_key;
+    header
+        + &format!(
+            r#"jobs:
+  build:
+    name: {name} ${{{{ matrix.{key} }}}} on ${{{{ matrix.os }}}}
+    runs-on: ${{{{ matrix.os }}}}
+    strategy:
+      fail-fast: false
+      matrix:
+        os: {RUNNERS}
+        {key}: [ {versions} ]
+    steps:
+      - uses: actions/checkout@v4
+"#,
+            name = toolchain.name,
+        )
+        + &toolchain
+            .setup
+            .replace("{version}", &format!("${{{{ matrix.{} }}}}", key))
+        + &toolchain.build
+        + &toolchain.test
+}
+
+// build and test with the newest toolchain, then deploy from the default
+// branch. The deployment itself depends on the target and is left to fill in.
+fn deploy(toolchain: Option<&Toolchain>, branch: &str) -> String {
+    let mut workflow = format!(
+        r#"name: Deploy
+
+on:
+  push:
+    branches: [ {branch} ]
+  workflow_dispatch:
+
+concurrency:
+  group: deploy
+  cancel-in-progress: false
+
+jobs:
+  deploy:
+    runs-on: ubuntu-latest
+    environment: production
+    steps:
+      - uses: actions/checkout@v4
+"#
+    );
+    if let Some(toolchain) = toolchain {
+        let version = toolchain.versions.last().copied().unwrap_or_default();
+        workflow += &toolchain.setup.replace("{version}", version);
+        workflow += &toolchain.build;
+        workflow += &toolchain.test;
+    }
+    workflow
+        + r#"      # replace with the commands that deploy this project, using
+      # secrets from the production environment
+      - name: Deploy
+        run: echo "Add the commands that deploy this project"
+"#
+}
+
+// a starting point that can be run by hand, with the toolchain set up
+fn custom(toolchain: Option<&Toolchain>, branch: &str) -> String {
+    let mut workflow = format!(
+        r#"name: Custom Workflow
+
+on:
+  push:
+    branches: [ {branch} ]
+  workflow_dispatch:
+
+jobs:
+  custom:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+"#
+    );
+    if let Some(toolchain) = toolchain {
+        let version = toolchain.versions.last().copied().unwrap_or_default();
+        workflow += &toolchain.setup.replace("{version}", version);
+    }
+    workflow
+        + r#"      - name: Custom step
+        run: echo "Add your own steps here"
+"#
+}
+
+const WORKFLOW_KEYS: &[&str] = &[
+    "name",
+    "run-name",
+    "on",
+    "permissions",
+    "env",
+    "defaults",
+    "concurrency",
+    "jobs",
+];
+
+/// Problems that would stop GitHub from running the workflow in `contents`:
+/// invalid YAML, missing `on` or `jobs`, jobs without `runs-on` or steps,
+/// steps without `uses` or `run`, and malformed `${{ }}` expressions.
+pub fn validate(contents: &str) -> Vec<String> {
+    let workflow: Value = match serde_norway::from_str(contents) {
+        Ok(workflow) => workflow,
+        Err(e) => return vec![format!("invalid YAML: {}", e)],
+    };
+    let workflow = match workflow.as_mapping() {
+        Some(workflow) => workflow,
+        None => return vec!["the workflow must be a mapping of keys".to_string()],
+    };
+
+    let mut errors = Vec::new();
+    for key in workflow.keys() {
+        match key.as_str() {
+            Some(key) if WORKFLOW_KEYS.contains(&key) => {}
+            _ => errors.push(format!("unknown top-level key '{}'", describe(key))),
+        }
+    }
+    if !workflow.contains_key("on") {
+        errors.push("missing 'on' (the events that trigger the workflow)".to_string());
+    }
+
+    match workflow.get("jobs") {
+        None => errors.push("missing 'jobs'".to_string()),
+        Some(Value::Mapping(jobs)) if !jobs.is_empty() => {
+            for (id, job) in jobs {
+                validate_job(&describe(id), job, jobs, &mut errors);
+            }
+        }
+        Some(_) => errors.push("'jobs' must be a mapping with at least one job".to_string()),
+    }
+
+    check_expressions("", &Value::Mapping(workflow.clone()), &mut errors);
+    errors
+}
+
+fn validate_job(id: &str, job: &Value, jobs: &Mapping, errors: &mut Vec<String>) {
+    let at = format!("jobs.{}", id);
+    let job = match job.as_mapping() {
+        Some(job) => job,
+        None => return errors.push(format!("{}: a job must be a mapping", at)),
+    };
+
+    let needs = match job.get("needs") {
+        Some(Value::String(need)) => vec![need.as_str()],
+        Some(Value::Sequence(needs)) => needs.iter().filter_map(|need| need.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    for need in needs {
+        if !jobs.contains_key(need) {
+            errors.push(format!("{}: needs unknown job '{}'", at, need));
+        }
+    }
+
+    // a job that calls a reusable workflow has neither
+    if job.contains_key("uses") {
+        return;
+    }
+    if !job.contains_key("runs-on") {
+        errors.push(format!("{}: missing 'runs-on'", at));
+    }
+    let steps = match job.get("steps") {
+        Some(Value::Sequence(steps)) if !steps.is_empty() => steps,
+        Some(_) => return errors.push(format!("{}: 'steps' must be a list of steps", at)),
+        None => return errors.push(format!("{}: missing 'steps'", at)),
+    };
+    for (index, step) in steps.iter().enumerate() {
+        let at = format!("{}.steps[{}]", at, index);
+        match step.as_mapping() {
+            None => errors.push(format!("{}: a step must be a mapping", at)),
+            Some(step) => match (step.contains_key("uses"), step.contains_key("run")) {
+                (true, true) => errors.push(format!("{}: has both 'uses' and 'run'", at)),
+                (false, false) => errors.push(format!("{}: needs 'uses' or 'run'", at)),
+                _ => {}
+            },
+        }
+    }
+}
+
+// check every `${{ }}` in string values, with `at` the path to `value`
+fn check_expressions(at: &str, value: &Value, errors: &mut Vec<String>) {
+    match value {
+        Value::String(text) => {
+            if let Err(e) = check_expression_syntax(text) {
+                errors.push(format!("{}: {}", at, e));
+            }
+        }
+        Value::Sequence(items) => {
+            for (index, item) in items.iter().enumerate() {
+                check_expressions(&format!("{}[{}]", at, index), item, errors);
+            }
+        }
+        Value::Mapping(entries) => {
+            for (key, item) in entries {
+                let key = describe(key);
+                let at = if at.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", at, key)
+                };
+                check_expressions(&at, item, errors);
+            }
+        }
+        Value::Tagged(tagged) => check_expressions(at, &tagged.value, errors),
+        _ => {}
+    }
+}
+
+// each `${{` needs a closing `}}` outside of string literals, with
+// something in between and balanced parentheses
+fn check_expression_syntax(text: &str) -> Result<(), String> {
+    let mut rest = text;
+    while let Some(start) = rest.find("${{") {
+        let expression = &rest[start + 3..];
+        let mut quoted = false;
+        let mut depth = 0i32;
+        let mut end = None;
+        let mut chars = expression.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                // '' is an escaped quote inside a string literal
+                '\'' => quoted = !quoted,
+                '(' if !quoted => depth += 1,
+                ')' if !quoted => {
+                    depth -= 1;
+                    if depth < 0 {
+                        return Err(format!(
+                            "unbalanced ')' in expression '${{{{{}'",
+                            expression
+                        ));
+                    }
+                }
+                '}' if !quoted && chars.peek().is_some_and(|(_, next)| *next == '}') => {
+                    end = Some(index);
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let end = end.ok_or_else(|| {
+            if quoted {
+                format!("unterminated string in expression '${{{{{}'", expression)
+            } else {
+                format!(
+                    "expression '${{{{{}' is missing its closing '}}}}'",
+                    expression
+                )
+            }
+        })?;
+        let inner = &expression[..end];
+        if inner.trim().is_empty() {
+            return Err("empty expression '${{ }}'".to_string());
+        }
+        if depth != 0 {
+            return Err(format!("unbalanced '(' in expression '${{{{{}}}}}'", inner));
+        }
+        rest = &expression[end + 2..];
+    }
+    Ok(())
+}
+
+fn describe(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        other => serde_norway::to_string(other)
+            .map(|key| key.trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detect::detect_language;
+
+    fn workflow(jobs: &str) -> String {
+        format!("on: push\njobs:\n{}", jobs)
+    }
+
+    fn variables(language: Option<&str>) -> Variables {
+        Variables {
+            project: "demo".to_string(),
+            default_branch: "main".to_string(),
+            language: language.map(|language| language.to_string()),
+            language_version: language.map(|_| "1.0".to_string()),
+        }
+    }
+
+    #[test]
+    fn valid_workflow_has_no_errors() {
+        let contents = workflow(
+            "  build:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@v4\n      - run: make\n  test:\n    needs: build\n    runs-on: ubuntu-latest\n    steps:\n      - run: make test\n",
+        );
+        assert_eq!(validate(&contents), Vec::<String>::new());
+    }
+
+    #[test]
+    fn missing_on_and_jobs() {
+        assert_eq!(
+            validate("name: CI\n"),
+            [
+                "missing 'on' (the events that trigger the workflow)",
+                "missing 'jobs'",
+            ]
+        );
+        assert_eq!(
+            validate("on: push\njobs: {}\n"),
+            ["'jobs' must be a mapping with at least one job"]
+        );
+        assert_eq!(
+            validate("on: push\njobs: x\nbuild: y\n"),
+            [
+                "unknown top-level key 'build'",
+                "'jobs' must be a mapping with at least one job",
+            ]
+        );
+        assert!(validate("on: [push\n")[0].starts_with("invalid YAML"));
+    }
+
+    #[test]
+    fn job_needs_runs_on_unless_reusable() {
+        assert_eq!(
+            validate(&workflow("  build:\n    steps:\n      - run: make\n")),
+            ["jobs.build: missing 'runs-on'"]
+        );
+        assert_eq!(
+            validate(&workflow(
+                "  call:\n    uses: acme/workflows/.github/workflows/ci.yml@main\n"
+            )),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            validate(&workflow("  build:\n    runs-on: ubuntu-latest\n")),
+            ["jobs.build: missing 'steps'"]
+        );
+    }
+
+    #[test]
+    fn step_needs_exactly_one_of_uses_and_run() {
+        assert_eq!(
+            validate(&workflow(
+                "  build:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@v4\n        run: make\n      - name: Nothing\n"
+            )),
+            [
+                "jobs.build.steps[0]: has both 'uses' and 'run'",
+                "jobs.build.steps[1]: needs 'uses' or 'run'",
+            ]
+        );
+    }
+
+    #[test]
+    fn needs_must_name_a_job() {
+        assert_eq!(
+            validate(&workflow(
+                "  test:\n    needs: [build]\n    runs-on: ubuntu-latest\n    steps:\n      - run: make test\n"
+            )),
+            ["jobs.test: needs unknown job 'build'"]
+        );
+    }
+
+    #[test]
+    fn expressions_must_be_closed_and_balanced() {
+        assert!(check_expression_syntax("${{ github.ref }} and ${{ (a || b) && c }}").is_ok());
+        // `}}` and parentheses inside string literals don't count
+        assert!(check_expression_syntax("${{ format('}}{0}(', github.ref) }}").is_ok());
+        assert!(check_expression_syntax("${{ 'it''s' }}").is_ok());
+
+        assert!(check_expression_syntax("${{ github.ref")
+            .unwrap_err()
+            .contains("missing its closing"));
+        assert!(check_expression_syntax("${{ format('}}') ")
+            .unwrap_err()
+            .contains("missing its closing"));
+        assert!(check_expression_syntax("${{ 'open }}")
+            .unwrap_err()
+            .contains("unterminated string"));
+        assert!(check_expression_syntax("${{ (a || b }}")
+            .unwrap_err()
+            .contains("unbalanced '('"));
+        assert!(check_expression_syntax("${{ a) }}")
+            .unwrap_err()
+            .contains("unbalanced ')'"));
+        assert!(check_expression_syntax("${{  }}")
+            .unwrap_err()
+            .contains("empty expression"));
+    }
+
+    #[test]
+    fn expression_errors_name_their_place() {
+        assert_eq!(
+            validate(&workflow(
+                "  build:\n    runs-on: ${{ matrix.os\n    steps:\n      - run: make\n"
+            )),
+            ["jobs.build.runs-on: expression '${{ matrix.os' is missing its closing '}}'"]
+        );
+    }
+
+    #[test]
+    fn render_fills_placeholders() {
+        assert_eq!(
+            render(
+                "{{ project }} on {{default_branch}}: {{ language }}",
+                &variables(Some("Rust"))
+            )
+            .unwrap(),
+            "demo on main: Rust"
+        );
+    }
+
+    #[test]
+    fn render_leaves_expressions_alone() {
+        assert_eq!(
+            render(
+                "${{ github.ref }} {{ project }} ${{matrix.os}}",
+                &variables(None)
+            )
+            .unwrap(),
+            "${{ github.ref }} demo ${{matrix.os}}"
+        );
+    }
+
+    #[test]
+    fn render_rejects_unknown_and_unclosed_placeholders() {
+        assert!(render("{{ nope }}", &variables(None))
+            .unwrap_err()
+            .contains("Unknown variable"));
+        assert!(render("{{ language }}", &variables(None))
+            .unwrap_err()
+            .contains("wasn't detected"));
+        assert!(render("{{ project", &variables(None))
+            .unwrap_err()
+            .contains("Unclosed"));
+    }
+
+    // a project directory holding `files`, with the detected language
+    fn project(name: &str, files: &[&str]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("git_mate-workflow-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn builtins_are_valid_for_every_toolchain() {
+        let projects: &[(&str, &[&str])] = &[
+            ("none", &[]),
+            ("ruby", &["Gemfile"]),
+            ("rust", &["Cargo.toml"]),
+            ("node", &["package.json"]),
+            ("node-lock", &["package.json", "package-lock.json"]),
+            ("python", &["pyproject.toml"]),
+            ("python-requirements", &["requirements.txt"]),
+            ("go", &["go.mod"]),
+            ("maven", &["pom.xml"]),
+            ("gradle", &["build.gradle"]),
+            ("gradlew", &["build.gradle.kts", "gradlew"]),
+        ];
+        for (name, files) in projects {
+            let dir = project(name, files);
+            for (workflow_type, _) in BUILTINS {
+                let (_, contents) = builtin(workflow_type, detect_language(&dir), "main", &dir);
+                assert_eq!(
+                    validate(&contents),
+                    Vec::<String>::new(),
+                    "{} workflow for {}:\n{}",
+                    workflow_type,
+                    name,
+                    contents
+                );
+            }
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+}